@@ -16,7 +16,14 @@ impl<T> Animate<T> where T: Parse + Clone {
     pub fn parse_animate(node: &Node, value: &T) -> Result<Self, Error> {
         let timing = Timing::parse_node(node)?;
         let calc_mode = parse_attr_or(node, "calcMode", CalcMode::Linear)?;
-        let mode = AnimationMode::parse_node(node, value, calc_mode)?;
+        let mode = match node.tag_name().name() {
+            // <set> holds the `to` value for its whole duration
+            "set" => {
+                let to = T::parse(get_attr(node, "to")?)?;
+                AnimationMode::Absolute { from: to.clone(), to }
+            }
+            _ => AnimationMode::parse_node(node, value, calc_mode)?
+        };
         let fill = parse_attr_or(node, "fill", AnimationFill::Remove)?;
         let default_additive = match mode {
             AnimationMode::Absolute { .. } | AnimationMode::Values { .. } => Additive::Replace,
@@ -48,8 +55,12 @@ pub struct Timing {
 impl ParseNode for Timing {
     fn parse_node(node: &Node) -> Result<Timing, Error> {
         let begin = parse_attr_or(node, "begin", Time(0.0))?;
-        let duration: Time = parse_attr(node, "dur")?;
-        Ok(Timing { begin, scale: 1.0 / duration.seconds() })
+        let scale = match node.attribute("dur") {
+            // an indefinite animation never reaches its end
+            None | Some("indefinite") => 0.0,
+            Some(dur) => 1.0 / Time::parse(dur)?.seconds()
+        };
+        Ok(Timing { begin, scale })
     }
 }
 pub struct AnimateMotion {
@@ -145,6 +156,9 @@ impl UnitSpline {
 
 impl Timing {
     pub fn pos(&self, t: Time) -> f32 {
+        if t < self.begin {
+            return -1.0;
+        }
         (t - self.begin).seconds() * self.scale
     }
 }
@@ -1,7 +1,8 @@
 use crate::prelude::*;
 
 use pathfinder_content::{
-    fill::{FillRule}
+    fill::{FillRule},
+    effects::{BlendMode},
 };
 use svgtypes::{Length};
 use isolang::Language;
@@ -12,6 +13,7 @@ pub struct Attrs {
     pub clip_rule: Option<FillRule>,
     pub transform: Transform,
     pub opacity: Value<Option<f32>>,
+    pub color: Value<CurrentColor>,
     pub fill: Value<Fill>,
     pub fill_rule: Option<FillRule>,
     pub fill_opacity: Value<Option<f32>>,
@@ -20,11 +22,35 @@ pub struct Attrs {
     pub stroke_opacity: Value<Option<f32>>,
     pub stroke_dasharray: Value<Option<DashArray>>,
     pub stroke_dashoffset: Value<Option<Length>>,
-    pub display: bool,
+    pub display: Value<Display>,
+    pub visibility: Value<VisibilityAttr>,
     pub filter: Option<Iri>,
-    pub font_size: Value<Option<LengthY>>,
+    pub mask: Option<Iri>,
+    pub font_size: Value<Option<FontSize>>,
+    pub font_family: Option<FontFamilyList>,
+    pub font_weight: Option<FontWeight>,
+    pub font_style: Option<FontStyle>,
+    pub font_stretch: Option<FontStretch>,
+    pub font_kerning: Option<bool>,
+    pub kerning: Option<Kerning>,
+    pub letter_spacing: Option<Length>,
+    pub word_spacing: Option<Length>,
     pub direction: Option<TextFlow>,
+    pub writing_mode: Option<WritingMode>,
+    pub text_orientation: Option<TextOrientation>,
+    pub text_anchor: Option<TextAnchor>,
+    pub text_align: Option<TextAlign>,
+    pub text_decoration: TextDecoration,
+    pub dominant_baseline: Option<Baseline>,
+    pub alignment_baseline: Baseline,
+    pub baseline_shift: Value<BaselineShift>,
     pub lang: Option<Language>,
+    pub paint_order: Option<PaintOrder>,
+    pub mix_blend_mode: BlendMode,
+    pub isolation: bool,
+    pub overflow: Option<Overflow>,
+    pub color_interpolation: Option<ColorInterpolation>,
+    pub color_interpolation_filters: Option<ColorInterpolation>,
 }
 
 #[derive(Debug, Clone)]
@@ -37,7 +63,7 @@ impl Parse for Fill {
 
 fn parse_paint(s: &str) -> Result<Option<Paint>, Error> {
     match s {
-        "inherit" | "currentColor" | "currentcolor" => Ok(None),
+        "inherit" => Ok(None),
         _ => Paint::parse(s).map(Some)
     }
 }
@@ -50,6 +76,18 @@ impl Parse for Stroke {
     }
 }
 
+/// the `color` property, `None` if inherited
+#[derive(Debug, Clone)]
+pub struct CurrentColor(pub Option<Color>);
+impl Parse for CurrentColor {
+    fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "inherit" | "currentColor" | "currentcolor" => Ok(CurrentColor(None)),
+            _ => Color::parse(s).map(|c| CurrentColor(Some(c)))
+        }
+    }
+}
+
 fn parse_lang_attr(s: &str) -> Option<Language> {
     Language::from_639_3(s).or_else(|| Language::from_639_1(s))
 }
@@ -68,6 +106,7 @@ impl Attrs {
             var clip_rule ("clip-rule"): Option<FillRule>,
             anim transform: Transform,
             anim opacity: Value<Option<f32>>,
+            anim color: Value<CurrentColor> = Value::new(CurrentColor(None)),
             anim fill: Value<Fill> = Value::new(Fill(None)),
             var fill_rule ("fill-rule"): Option<FillRule> = Some(FillRule::Winding) => inherit(FillRule::parse),
             anim fill_opacity ("fill-opacity"): Value<Option<f32>>,
@@ -76,17 +115,60 @@ impl Attrs {
             anim stroke_opacity ("stroke-opacity"): Value<Option<f32>>,
             anim stroke_dasharray ("stroke-dasharray"): Value<Option<DashArray>>,
             anim stroke_dashoffset ("stroke-dashoffset"): Value<Option<Length>>,
-            var display: bool = true => parse_display,
+            anim display: Value<Display> = Value::new(Display(true)),
+            anim visibility: Value<VisibilityAttr> = Value::new(VisibilityAttr(None)),
             var filter: Option<Iri>,
-            anim font_size ("font-size"): Value<Option<LengthY>>,
+            var mask: Option<Iri>,
+            anim font_size ("font-size"): Value<Option<FontSize>>,
+            var font_family ("font-family"): Option<FontFamilyList> => inherit(FontFamilyList::parse),
+            var font_weight ("font-weight"): Option<FontWeight> => inherit(FontWeight::parse),
+            var font_style ("font-style"): Option<FontStyle> => inherit(FontStyle::parse),
+            var font_stretch ("font-stretch"): Option<FontStretch> => inherit(FontStretch::parse),
+            var font_kerning ("font-kerning"): Option<bool> => inherit(parse_font_kerning),
+            var kerning: Option<Kerning> => inherit(Kerning::parse),
+            var letter_spacing ("letter-spacing"): Option<Length> => inherit(parse_spacing),
+            var word_spacing ("word-spacing"): Option<Length> => inherit(parse_spacing),
             var direction: Option<TextFlow>,
+            var writing_mode ("writing-mode"): Option<WritingMode> => inherit(WritingMode::parse),
+            var text_orientation ("text-orientation"): Option<TextOrientation> => inherit(TextOrientation::parse),
+            var glyph_orientation_vertical ("glyph-orientation-vertical"): Option<TextOrientation> => inherit(parse_glyph_orientation),
+            var text_anchor ("text-anchor"): Option<TextAnchor> => inherit(TextAnchor::parse),
+            var text_align ("text-align"): Option<TextAlign> => inherit(TextAlign::parse),
+            var text_decoration ("text-decoration"): TextDecoration = TextDecoration::default(),
+            var dominant_baseline ("dominant-baseline"): Option<Baseline> => inherit(Baseline::parse),
+            var alignment_baseline ("alignment-baseline"): Baseline = Baseline::Auto,
+            anim baseline_shift ("baseline-shift"): Value<BaselineShift> = Value::new(BaselineShift::Baseline),
             var lang: Option<Language>,
+            var paint_order ("paint-order"): Option<PaintOrder> => inherit(PaintOrder::parse),
+            var mix_blend_mode ("mix-blend-mode"): BlendMode = BlendMode::SrcOver,
+            var isolation: bool = false => parse_isolation,
+            var overflow: Option<Overflow>,
+            var color_interpolation ("color-interpolation"): Option<ColorInterpolation> => inherit(ColorInterpolation::parse),
+            var color_interpolation_filters ("color-interpolation-filters"): Option<ColorInterpolation> => inherit(ColorInterpolation::parse),
         });
+        let (font, [size, family, weight, style, stretch]) = font_shorthand(node)?;
+        if size {
+            font_size.value = font.as_ref().map(|font| font.size);
+        }
+        if family {
+            font_family = font.as_ref().map(|font| font.family.clone());
+        }
+        if weight {
+            font_weight = font.as_ref().map(|font| font.weight);
+        }
+        if style {
+            font_style = font.as_ref().map(|font| font.style);
+        }
+        if stretch {
+            font_stretch = font.as_ref().map(|font| font.stretch);
+        }
+        let text_orientation = text_orientation.or(glyph_orientation_vertical);
         Ok(Attrs {
             clip_path,
             clip_rule,
             transform,
             opacity,
+            color,
             fill,
             fill_rule,
             fill_opacity,
@@ -96,10 +178,34 @@ impl Attrs {
             stroke_dasharray,
             stroke_dashoffset,
             display,
+            visibility,
             filter,
+            mask,
             font_size,
+            font_family,
+            font_weight,
+            font_style,
+            font_stretch,
+            font_kerning,
+            kerning,
+            letter_spacing,
+            word_spacing,
             direction,
+            writing_mode,
+            text_orientation,
+            text_anchor,
+            text_align,
+            text_decoration,
+            dominant_baseline,
+            alignment_baseline,
+            baseline_shift,
             lang,
+            paint_order,
+            mix_blend_mode,
+            isolation,
+            overflow,
+            color_interpolation,
+            color_interpolation_filters,
         })
     }
 }
@@ -131,14 +237,157 @@ impl Parse for FillRule {
     }
 }
 
-fn parse_display(s: &str) -> Result<bool, Error> {
+impl Parse for BlendMode {
+    fn parse(s: &str) -> Result<BlendMode, Error> {
+        Ok(match s {
+            "normal" => BlendMode::SrcOver,
+            "multiply" => BlendMode::Multiply,
+            "screen" => BlendMode::Screen,
+            "overlay" => BlendMode::Overlay,
+            "darken" => BlendMode::Darken,
+            "lighten" => BlendMode::Lighten,
+            "color-dodge" => BlendMode::ColorDodge,
+            "color-burn" => BlendMode::ColorBurn,
+            "hard-light" => BlendMode::HardLight,
+            "soft-light" => BlendMode::SoftLight,
+            "difference" => BlendMode::Difference,
+            "exclusion" => BlendMode::Exclusion,
+            "hue" => BlendMode::Hue,
+            "saturation" => BlendMode::Saturation,
+            "color" => BlendMode::Color,
+            "luminosity" => BlendMode::Luminosity,
+            "plus-lighter" => BlendMode::Lighter,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+
+/// whether content outside of a viewport is shown
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Overflow {
+    Visible,
+    Hidden,
+}
+impl Parse for Overflow {
+    fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "visible" | "auto" => Ok(Overflow::Visible),
+            "hidden" | "clip" | "scroll" => Ok(Overflow::Hidden),
+            _ => Err(Error::InvalidAttributeValue(s.into()))
+        }
+    }
+}
+
+/// color space in which colors are interpolated and filters operate.
+/// only `feColorMatrix` honors `linearRGB` for filters, blurs are always computed in sRGB.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ColorInterpolation {
+    Auto,
+    SRGB,
+    LinearRGB,
+}
+impl Parse for ColorInterpolation {
+    fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "auto" => Ok(ColorInterpolation::Auto),
+            "sRGB" => Ok(ColorInterpolation::SRGB),
+            "linearRGB" => Ok(ColorInterpolation::LinearRGB),
+            _ => Err(Error::InvalidAttributeValue(s.into()))
+        }
+    }
+}
+
+/// the `font` shorthand of a node and which longhands it sets: the size, family, weight, style and stretch.
+/// a longhand declared after the shorthand wins, and the style attribute comes after the attributes.
+/// system fonts are ignored.
+fn font_shorthand(node: &Node) -> Result<(Option<FontShorthand>, [bool; 5]), Error> {
+    const LONGHANDS: [&str; 5] = ["font-size", "font-family", "font-weight", "font-style", "font-stretch"];
+    let attributes = node.attributes().iter().map(|attr| (attr.name(), attr.value()));
+    let declarations = node.attribute("style").into_iter().flat_map(style_list);
+
+    let mut font = None;
+    let mut sets = [false; 5];
+    for (key, val) in attributes.chain(declarations) {
+        if key == "font" {
+            if !FontShorthand::is_system_font(val) {
+                font = inherit(FontShorthand::parse)(val)?;
+                sets = [true; 5];
+            }
+        } else if let Some(i) = LONGHANDS.iter().position(|&name| name == key) {
+            sets[i] = false;
+        }
+    }
+    Ok((font, sets))
+}
+
+fn parse_font_kerning(s: &str) -> Result<bool, Error> {
     match s {
+        "auto" | "normal" => Ok(true),
         "none" => Ok(false),
-        "inline" => Ok(true),
         val => Err(Error::InvalidAttributeValue(val.into()))
     }
 }
 
+/// `letter-spacing` and `word-spacing`, where `normal` means no extra space
+fn parse_spacing(s: &str) -> Result<Length, Error> {
+    match s {
+        "normal" => Ok(Length::zero()),
+        _ => Length::parse(s)
+    }
+}
+
+fn parse_isolation(s: &str) -> Result<bool, Error> {
+    match s {
+        "auto" => Ok(false),
+        "isolate" => Ok(true),
+        val => Err(Error::InvalidAttributeValue(val.into()))
+    }
+}
+
+/// the `display` property: whether the element is rendered at all
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Display(pub bool);
+impl Parse for Display {
+    fn parse(s: &str) -> Result<Self, Error> {
+        const KEYWORDS: &[&str] = &[
+            "inline", "block", "run-in", "flow", "flow-root", "table", "flex", "grid", "ruby",
+            "list-item", "contents", "inline-block", "inline-table", "inline-flex", "inline-grid",
+            "table-row-group", "table-header-group", "table-footer-group", "table-row", "table-cell",
+            "table-column-group", "table-column", "table-caption", "ruby-base", "ruby-text",
+            "ruby-base-container", "ruby-text-container", "compact", "marker",
+            "inherit", "initial", "unset", "revert",
+        ];
+        match s {
+            "none" => Ok(Display(false)),
+            // multi-keyword syntax like `block flow`
+            _ if s.split_whitespace().all(|k| KEYWORDS.contains(&k)) => Ok(Display(true)),
+            _ => Err(Error::InvalidAttributeValue(s.into()))
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Visibility {
+    Visible,
+    Hidden,
+    Collapse,
+}
+
+/// the `visibility` property, `None` if inherited
+#[derive(Debug, Clone)]
+pub struct VisibilityAttr(pub Option<Visibility>);
+impl Parse for VisibilityAttr {
+    fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "visible" => Ok(VisibilityAttr(Some(Visibility::Visible))),
+            "hidden" => Ok(VisibilityAttr(Some(Visibility::Hidden))),
+            "collapse" => Ok(VisibilityAttr(Some(Visibility::Collapse))),
+            "inherit" => Ok(VisibilityAttr(None)),
+            _ => Err(Error::InvalidAttributeValue(s.into()))
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ClipPathAttr {
     None,
@@ -176,4 +425,260 @@ impl Parse for TextFlow {
             val => return Err(Error::InvalidAttributeValue(val.into()))
         })
     }
-}
\ No newline at end of file
+}
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WritingMode {
+    HorizontalTb,
+    /// lines go from right to left, only one line is laid out so it is the same as `VerticalLr`
+    VerticalRl,
+    VerticalLr,
+}
+impl WritingMode {
+    pub fn is_vertical(self) -> bool {
+        self != WritingMode::HorizontalTb
+    }
+}
+impl Parse for WritingMode {
+    fn parse(s: &str) -> Result<WritingMode, Error> {
+        Ok(match s {
+            // the SVG 1.1 values
+            "horizontal-tb" | "lr-tb" | "lr" | "rl-tb" | "rl" => WritingMode::HorizontalTb,
+            "vertical-rl" | "tb-rl" | "tb" => WritingMode::VerticalRl,
+            "vertical-lr" => WritingMode::VerticalLr,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+
+/// orientation of the glyphs in vertical text
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TextOrientation {
+    Mixed,
+    Upright,
+    Sideways,
+}
+impl Parse for TextOrientation {
+    fn parse(s: &str) -> Result<TextOrientation, Error> {
+        Ok(match s {
+            "mixed" => TextOrientation::Mixed,
+            "upright" => TextOrientation::Upright,
+            "sideways" | "sideways-right" => TextOrientation::Sideways,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+
+/// the SVG 1.1 `glyph-orientation-vertical`, only the angles 0 and 90 are supported
+fn parse_glyph_orientation(s: &str) -> Result<TextOrientation, Error> {
+    Ok(match s {
+        "auto" => TextOrientation::Mixed,
+        "0" | "0deg" => TextOrientation::Upright,
+        "90" | "90deg" => TextOrientation::Sideways,
+        val => return Err(Error::InvalidAttributeValue(val.into()))
+    })
+}
+
+/// alignment of a text chunk relative to its position, in the direction of the text
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TextAnchor {
+    Start,
+    Middle,
+    End,
+}
+impl Parse for TextAnchor {
+    fn parse(s: &str) -> Result<TextAnchor, Error> {
+        Ok(match s {
+            "start" => TextAnchor::Start,
+            "middle" => TextAnchor::Middle,
+            "end" => TextAnchor::End,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+
+/// alignment of the lines in a `<textArea>`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TextAlign {
+    Start,
+    Center,
+    End,
+    Left,
+    Right,
+}
+impl Parse for TextAlign {
+    fn parse(s: &str) -> Result<TextAlign, Error> {
+        Ok(match s {
+            // justified text is aligned to the start
+            "start" | "justify" => TextAlign::Start,
+            "center" => TextAlign::Center,
+            "end" => TextAlign::End,
+            "left" => TextAlign::Left,
+            "right" => TextAlign::Right,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+
+/// lines of the `text-decoration` property. it is not inherited, but the lines are drawn across the descendants.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct TextDecoration {
+    pub underline: bool,
+    pub overline: bool,
+    pub line_through: bool,
+}
+impl TextDecoration {
+    pub fn is_none(&self) -> bool {
+        !(self.underline || self.overline || self.line_through)
+    }
+}
+impl Parse for TextDecoration {
+    fn parse(s: &str) -> Result<TextDecoration, Error> {
+        let mut decoration = TextDecoration::default();
+        for word in s.split_whitespace() {
+            match word {
+                "none" => {}
+                "underline" => decoration.underline = true,
+                "overline" => decoration.overline = true,
+                "line-through" => decoration.line_through = true,
+                // the line style, color and thickness are ignored, all lines are solid
+                // and painted like the text
+                _ => {}
+            }
+        }
+        Ok(decoration)
+    }
+}
+
+/// baseline used to align glyphs, for `dominant-baseline` and `alignment-baseline`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Baseline {
+    Auto,
+    Alphabetic,
+    Ideographic,
+    Hanging,
+    Mathematical,
+    Central,
+    Middle,
+    TextTop,
+    TextBottom,
+}
+impl Parse for Baseline {
+    fn parse(s: &str) -> Result<Baseline, Error> {
+        Ok(match s {
+            // the SVG 1.1 keywords that depend on the script or font size are treated as `auto`
+            "auto" | "baseline" | "use-script" | "no-change" | "reset-size" => Baseline::Auto,
+            "alphabetic" => Baseline::Alphabetic,
+            "ideographic" => Baseline::Ideographic,
+            "hanging" => Baseline::Hanging,
+            "mathematical" => Baseline::Mathematical,
+            "central" | "center" => Baseline::Central,
+            "middle" => Baseline::Middle,
+            "text-top" | "text-before-edge" | "before-edge" | "top" => Baseline::TextTop,
+            "text-bottom" | "text-after-edge" | "after-edge" | "bottom" => Baseline::TextBottom,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+
+/// the `baseline-shift` property. percentages refer to the font size.
+#[derive(Debug, Clone)]
+pub enum BaselineShift {
+    Baseline,
+    Sub,
+    Super,
+    Length(Length),
+}
+impl Parse for BaselineShift {
+    fn parse(s: &str) -> Result<BaselineShift, Error> {
+        Ok(match s {
+            "baseline" => BaselineShift::Baseline,
+            "sub" => BaselineShift::Sub,
+            "super" => BaselineShift::Super,
+            _ => BaselineShift::Length(Length::parse(s)?)
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PaintLayer {
+    Fill,
+    Stroke,
+    Markers,
+}
+
+/// order in which fill, stroke and markers are painted (first is at the bottom)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PaintOrder(pub [PaintLayer; 3]);
+impl PaintOrder {
+    pub fn layers(&self) -> &[PaintLayer] {
+        &self.0
+    }
+}
+impl Default for PaintOrder {
+    fn default() -> Self {
+        PaintOrder([PaintLayer::Fill, PaintLayer::Stroke, PaintLayer::Markers])
+    }
+}
+impl Parse for PaintOrder {
+    fn parse(s: &str) -> Result<PaintOrder, Error> {
+        if s == "normal" {
+            return Ok(PaintOrder::default());
+        }
+        let mut layers = Vec::with_capacity(3);
+        for word in s.split_whitespace() {
+            let layer = match word {
+                "fill" => PaintLayer::Fill,
+                "stroke" => PaintLayer::Stroke,
+                "markers" => PaintLayer::Markers,
+                _ => return Err(Error::InvalidAttributeValue(s.into()))
+            };
+            if layers.contains(&layer) {
+                return Err(Error::InvalidAttributeValue(s.into()));
+            }
+            layers.push(layer);
+        }
+        if layers.len() == 0 {
+            return Err(Error::InvalidAttributeValue(s.into()));
+        }
+        // omitted layers are painted afterwards in their default order
+        for &layer in PaintOrder::default().layers() {
+            if !layers.contains(&layer) {
+                layers.push(layer);
+            }
+        }
+        Ok(PaintOrder([layers[0], layers[1], layers[2]]))
+    }
+}
+#[test]
+fn test_display() {
+    assert_eq!(Display::parse("none").unwrap(), Display(false));
+    assert_eq!(Display::parse("block").unwrap(), Display(true));
+    assert_eq!(Display::parse("inline flow-root").unwrap(), Display(true));
+    assert!(Display::parse("hidden").is_err());
+}
+
+#[test]
+fn test_overflow() {
+    assert_eq!(Overflow::parse("auto").unwrap(), Overflow::Visible);
+    assert_eq!(Overflow::parse("clip").unwrap(), Overflow::Hidden);
+    assert!(Overflow::parse("sideways").is_err());
+}
+
+#[test]
+fn test_paint_order() {
+    use PaintLayer::*;
+    assert_eq!(PaintOrder::parse("normal").unwrap(), PaintOrder([Fill, Stroke, Markers]));
+    assert_eq!(PaintOrder::parse("stroke").unwrap(), PaintOrder([Stroke, Fill, Markers]));
+    assert_eq!(PaintOrder::parse("markers fill").unwrap(), PaintOrder([Markers, Fill, Stroke]));
+    assert!(PaintOrder::parse("fill fill").is_err());
+}
+
+#[test]
+fn test_text_decoration() {
+    let decoration = TextDecoration::parse("underline line-through").unwrap();
+    assert!(decoration.underline && decoration.line_through && !decoration.overline);
+    assert!(TextDecoration::parse("none").unwrap().is_none());
+    let decoration = TextDecoration::parse("underline wavy rgb(255, 0, 0)").unwrap();
+    assert!(decoration.underline && !decoration.line_through && !decoration.overline);
+    assert!(TextDecoration::parse("overline red").unwrap().overline);
+}
@@ -4,8 +4,9 @@ use svgtypes::NumberListParser;
 
 #[derive(Debug)]
 pub struct TagFilter {
-    pub filters: Vec<Filter>,
+    pub filters: Vec<FilterPrimitive>,
     pub id: Option<String>,
+    pub color_interpolation_filters: Option<ColorInterpolation>,
 }
 impl Tag for TagFilter {
     fn id(&self) -> Option<&str> {
@@ -24,15 +25,31 @@ impl ParseNode for TagFilter {
                     continue;
                 }
             };
-            filters.push(filter);
+            let color_interpolation_filters = parse_color_interpolation_filters(&elem)?;
+            filters.push(FilterPrimitive { filter, color_interpolation_filters });
         }
         
         let id = node.attribute("id").map(|s| s.to_owned());
+        let color_interpolation_filters = parse_color_interpolation_filters(node)?;
 
-        Ok(TagFilter { id, filters })
+        Ok(TagFilter { id, filters, color_interpolation_filters })
     }
 }
 
+fn parse_color_interpolation_filters(node: &Node) -> Result<Option<ColorInterpolation>, Error> {
+    parse!(node => {
+        var color_interpolation_filters ("color-interpolation-filters"): Option<ColorInterpolation> => inherit(ColorInterpolation::parse),
+    });
+    Ok(color_interpolation_filters)
+}
+
+/// a filter primitive and the color space it operates in, `None` if inherited from the filter
+#[derive(Debug)]
+pub struct FilterPrimitive {
+    pub filter: Filter,
+    pub color_interpolation_filters: Option<ColorInterpolation>,
+}
+
 #[derive(Debug)]
 pub enum Filter {
     GaussianBlur(FeGaussianBlur),
@@ -0,0 +1,452 @@
+use crate::prelude::*;
+use pathfinder_content::outline::Outline;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GenericFamily {
+    Serif,
+    SansSerif,
+    Monospace,
+    Cursive,
+    Fantasy,
+    SystemUi,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FontFamily {
+    Named(String),
+    Generic(GenericFamily),
+}
+
+/// the `font-family` property: families in order of preference
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontFamilyList(pub Vec<FontFamily>);
+impl Parse for FontFamilyList {
+    fn parse(s: &str) -> Result<Self, Error> {
+        let err = || Error::InvalidAttributeValue(s.into());
+        let mut families = vec![];
+        let mut rest = s.trim();
+        while rest.len() > 0 {
+            let (family, tail) = match rest.chars().next() {
+                Some(quote) if quote == '"' || quote == '\'' => {
+                    let end = rest[1..].find(quote).ok_or_else(err)? + 1;
+                    (FontFamily::Named(rest[1 .. end].into()), &rest[end + 1 ..])
+                }
+                _ => {
+                    let end = rest.find(',').unwrap_or(rest.len());
+                    let name = rest[.. end].split_whitespace().collect::<Vec<_>>().join(" ");
+                    let family = match name.as_str() {
+                        "" => return Err(err()),
+                        "serif" => FontFamily::Generic(GenericFamily::Serif),
+                        "sans-serif" => FontFamily::Generic(GenericFamily::SansSerif),
+                        "monospace" => FontFamily::Generic(GenericFamily::Monospace),
+                        "cursive" => FontFamily::Generic(GenericFamily::Cursive),
+                        "fantasy" => FontFamily::Generic(GenericFamily::Fantasy),
+                        "system-ui" => FontFamily::Generic(GenericFamily::SystemUi),
+                        _ => FontFamily::Named(name)
+                    };
+                    (family, &rest[end ..])
+                }
+            };
+            families.push(family);
+
+            let tail = tail.trim_start();
+            rest = if tail.starts_with(',') {
+                tail[1..].trim_start()
+            } else if tail.len() == 0 {
+                tail
+            } else {
+                return Err(err());
+            };
+        }
+        Ok(FontFamilyList(families))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FontWeight {
+    Absolute(u16),
+    Bolder,
+    Lighter,
+}
+impl FontWeight {
+    /// the numeric weight, relative weights depend on the `inherited` one
+    pub fn resolve(self, inherited: u16) -> u16 {
+        match self {
+            FontWeight::Absolute(w) => w,
+            FontWeight::Bolder => match inherited {
+                0 ..= 349 => 400,
+                350 ..= 549 => 700,
+                _ => 900,
+            },
+            FontWeight::Lighter => match inherited {
+                0 ..= 99 => inherited,
+                100 ..= 549 => 100,
+                550 ..= 749 => 400,
+                _ => 700,
+            },
+        }
+    }
+}
+impl Parse for FontWeight {
+    fn parse(s: &str) -> Result<Self, Error> {
+        Ok(match s {
+            "normal" => FontWeight::Absolute(400),
+            "bold" => FontWeight::Absolute(700),
+            "bolder" => FontWeight::Bolder,
+            "lighter" => FontWeight::Lighter,
+            _ => match s.parse::<u16>() {
+                Ok(w) if w >= 1 && w <= 1000 => FontWeight::Absolute(w),
+                _ => return Err(Error::InvalidAttributeValue(s.into()))
+            }
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+    Oblique,
+}
+impl Parse for FontStyle {
+    fn parse(s: &str) -> Result<Self, Error> {
+        Ok(match s {
+            "normal" => FontStyle::Normal,
+            "italic" => FontStyle::Italic,
+            // the angle is ignored
+            _ if s.split_whitespace().next() == Some("oblique") => FontStyle::Oblique,
+            _ => return Err(Error::InvalidAttributeValue(s.into()))
+        })
+    }
+}
+
+/// the `font-stretch` property, width in percent of the normal width
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FontStretch(pub f32);
+impl Parse for FontStretch {
+    fn parse(s: &str) -> Result<Self, Error> {
+        Ok(FontStretch(match s {
+            "ultra-condensed" => 50.,
+            "extra-condensed" => 62.5,
+            "condensed" => 75.,
+            "semi-condensed" => 87.5,
+            "normal" => 100.,
+            "semi-expanded" => 112.5,
+            "expanded" => 125.,
+            "extra-expanded" => 150.,
+            "ultra-expanded" => 200.,
+            _ if s.ends_with('%') => s[.. s.len() - 1].parse().map_err(|_| Error::InvalidAttributeValue(s.into()))?,
+            _ => return Err(Error::InvalidAttributeValue(s.into()))
+        }))
+    }
+}
+
+/// the SVG 1.1 `kerning` property. a length disables kerning and is added to the letter spacing.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Kerning {
+    Auto,
+    Length(Length),
+}
+impl Parse for Kerning {
+    fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "auto" => Ok(Kerning::Auto),
+            _ => Length::parse(s).map(Kerning::Length)
+        }
+    }
+}
+
+/// a `font-size`. percentages and `em` are relative to the font size of the parent.
+/// the keywords are converted to lengths, relative to a medium size of 16px.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontSize(pub Length);
+impl Parse for FontSize {
+    fn parse(s: &str) -> Result<Self, Error> {
+        let px = |scale: f64| Ok(FontSize(Length::new(16. * scale, LengthUnit::Px)));
+        match s {
+            "xx-small" => px(3. / 5.),
+            "x-small" => px(3. / 4.),
+            "small" => px(8. / 9.),
+            "medium" => px(1.),
+            "large" => px(6. / 5.),
+            "x-large" => px(3. / 2.),
+            "xx-large" => px(2.),
+            "xxx-large" => px(3.),
+            "larger" => Ok(FontSize(Length::new(120., LengthUnit::Percent))),
+            "smaller" => Ok(FontSize(Length::new(100. / 1.2, LengthUnit::Percent))),
+            _ => Length::parse(s).map(FontSize)
+        }
+    }
+}
+
+/// the `font` shorthand. the line height and variant are ignored.
+/// system fonts are not known, see `FontShorthand::is_system_font`.
+#[derive(Debug, Clone)]
+pub struct FontShorthand {
+    pub style: FontStyle,
+    pub weight: FontWeight,
+    pub stretch: FontStretch,
+    pub size: FontSize,
+    pub family: FontFamilyList,
+}
+impl Parse for FontShorthand {
+    fn parse(s: &str) -> Result<Self, Error> {
+        let err = || Error::InvalidAttributeValue(s.into());
+        let mut style = FontStyle::Normal;
+        let mut weight = FontWeight::Absolute(400);
+        let mut stretch = FontStretch(100.);
+
+        // style, variant, weight and stretch in any order, then the size
+        let mut rest = s.trim();
+        let size = loop {
+            let end = rest.find(char::is_whitespace).ok_or_else(err)?;
+            let word = &rest[.. end];
+            rest = rest[end ..].trim_start();
+            match word {
+                "normal" | "small-caps" => {}
+                _ => if let Ok(v) = FontStyle::parse(word) {
+                    style = v;
+                } else if let Ok(v) = FontWeight::parse(word) {
+                    weight = v;
+                } else if let Some(v) = FontStretch::parse(word).ok().filter(|_| !word.ends_with('%')) {
+                    // the shorthand only takes the keywords, a percentage is the size
+                    stretch = v;
+                } else {
+                    // the size may be followed by a line height
+                    break FontSize::parse(word.split('/').next().unwrap())?;
+                }
+            }
+        };
+        let family = FontFamilyList::parse(rest)?;
+        Ok(FontShorthand { style, weight, stretch, size, family })
+    }
+}
+impl FontShorthand {
+    /// whether `s` names a system font instead of listing the font properties
+    pub fn is_system_font(s: &str) -> bool {
+        match s.trim() {
+            "caption" | "icon" | "menu" | "message-box" | "small-caption" | "status-bar" => true,
+            _ => false
+        }
+    }
+}
+
+/// an SVG 1.1 `<font>`. the glyphs are in document order, which decides between overlapping matches.
+#[derive(Debug)]
+pub struct TagFont {
+    pub id: Option<String>,
+    pub horiz_adv_x: f32,
+    pub font_face: TagFontFace,
+    pub missing_glyph: Option<TagGlyph>,
+    pub glyphs: Vec<TagGlyph>,
+    pub hkern: Vec<TagHKern>,
+}
+impl Tag for TagFont {
+    fn id(&self) -> Option<&str> {
+        self.id.as_ref().map(|s| s.as_str())
+    }
+}
+impl ParseNode for TagFont {
+    fn parse_node(node: &Node) -> Result<TagFont, Error> {
+        parse!(node => {
+            var id,
+            var horiz_adv_x ("horiz-adv-x"): f32,
+        });
+        let mut font_face = None;
+        let mut missing_glyph = None;
+        let mut glyphs = vec![];
+        let mut hkern = vec![];
+        for elem in node.children().filter(|n| n.is_element()) {
+            match elem.tag_name().name() {
+                "font-face" => font_face = Some(TagFontFace::parse_node(&elem)?),
+                "missing-glyph" => missing_glyph = Some(TagGlyph::parse_node(&elem)?),
+                "glyph" => glyphs.push(TagGlyph::parse_node(&elem)?),
+                "hkern" => hkern.push(TagHKern::parse_node(&elem)?),
+                _ => {}
+            }
+        }
+        // without a <font-face> all values are the defaults
+        let font_face = match font_face {
+            Some(font_face) => font_face,
+            None => TagFontFace::parse_node(node)?
+        };
+        Ok(TagFont { id, horiz_adv_x, font_face, missing_glyph, glyphs, hkern })
+    }
+}
+
+/// the `<font-face>` of a `<font>`, all values are in font units
+#[derive(Debug)]
+pub struct TagFontFace {
+    pub family: Option<String>,
+    pub weight: Option<FontWeight>,
+    pub style: Option<FontStyle>,
+    pub units_per_em: f32,
+    pub ascent: Option<f32>,
+    pub descent: Option<f32>,
+    pub x_height: Option<f32>,
+    pub cap_height: Option<f32>,
+    pub underline_position: Option<f32>,
+    pub underline_thickness: Option<f32>,
+    pub strikethrough_position: Option<f32>,
+    pub strikethrough_thickness: Option<f32>,
+}
+impl ParseNode for TagFontFace {
+    fn parse_node(node: &Node) -> Result<TagFontFace, Error> {
+        parse!(node => {
+            var family ("font-family"): Option<String> => |s| FontFamilyList::parse(s).map(|list| match list.0.into_iter().next() {
+                Some(FontFamily::Named(name)) => Some(name),
+                _ => None
+            }),
+            var weight ("font-weight"): Option<FontWeight>,
+            var style ("font-style"): Option<FontStyle>,
+            var units_per_em ("units-per-em"): f32 = 1000.,
+            var ascent: Option<f32>,
+            var descent: Option<f32>,
+            var x_height ("x-height"): Option<f32>,
+            var cap_height ("cap-height"): Option<f32>,
+            var underline_position ("underline-position"): Option<f32>,
+            var underline_thickness ("underline-thickness"): Option<f32>,
+            var strikethrough_position ("strikethrough-position"): Option<f32>,
+            var strikethrough_thickness ("strikethrough-thickness"): Option<f32>,
+        });
+        Ok(TagFontFace {
+            family, weight, style, units_per_em, ascent, descent, x_height, cap_height,
+            underline_position, underline_thickness, strikethrough_position, strikethrough_thickness
+        })
+    }
+}
+
+/// the form of an arabic glyph, depending on how it joins its neighbours
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ArabicForm {
+    Initial,
+    Medial,
+    Terminal,
+    Isolated,
+}
+impl Parse for ArabicForm {
+    fn parse(s: &str) -> Result<Self, Error> {
+        Ok(match s {
+            "initial" => ArabicForm::Initial,
+            "medial" => ArabicForm::Medial,
+            "terminal" => ArabicForm::Terminal,
+            "isolated" => ArabicForm::Isolated,
+            _ => return Err(Error::InvalidAttributeValue(s.into()))
+        })
+    }
+}
+
+/// a `<glyph>` or `<missing-glyph>`
+#[derive(Debug)]
+pub struct TagGlyph {
+    /// the characters the glyph stands for, more than one for ligatures
+    pub unicode: Option<String>,
+    pub names: Vec<String>,
+    pub outline: Outline,
+    /// `None` to use the advance of the font
+    pub horiz_adv_x: Option<f32>,
+    /// `None` if the glyph can be used for all forms
+    pub arabic_form: Option<ArabicForm>,
+}
+impl ParseNode for TagGlyph {
+    fn parse_node(node: &Node) -> Result<TagGlyph, Error> {
+        parse!(node => {
+            var unicode: Option<String>,
+            var names ("glyph-name"): Vec<String> => name_list,
+            var d: Option<Outline> => |s| path_data(s).map(Some),
+            var horiz_adv_x ("horiz-adv-x"): Option<f32>,
+            var arabic_form ("arabic-form"): Option<ArabicForm>,
+        });
+        Ok(TagGlyph { unicode, names, outline: d.unwrap_or_else(Outline::new), horiz_adv_x, arabic_form })
+    }
+}
+
+/// a pair of glyphs that are moved closer by `k` font units.
+/// each side is given by characters and unicode ranges (`u1`, `u2`) or glyph names (`g1`, `g2`).
+#[derive(Debug)]
+pub struct TagHKern {
+    pub u1: Vec<(char, char)>,
+    pub g1: Vec<String>,
+    pub u2: Vec<(char, char)>,
+    pub g2: Vec<String>,
+    pub k: f32,
+}
+impl ParseNode for TagHKern {
+    fn parse_node(node: &Node) -> Result<TagHKern, Error> {
+        parse!(node => {
+            var u1: Vec<(char, char)> => unicode_list,
+            var g1: Vec<String> => name_list,
+            var u2: Vec<(char, char)> => unicode_list,
+            var g2: Vec<String> => name_list,
+            var k: f32,
+        });
+        Ok(TagHKern { u1, g1, u2, g2, k })
+    }
+}
+
+fn name_list(s: &str) -> Result<Vec<String>, Error> {
+    Ok(s.split(',').map(|s| s.trim()).filter(|s| s.len() > 0).map(|s| s.into()).collect())
+}
+
+/// characters and ranges like `U+0600-06FF` or `U+00??`, as inclusive ranges
+fn unicode_list(s: &str) -> Result<Vec<(char, char)>, Error> {
+    let err = || Error::InvalidAttributeValue(s.into());
+    let mut list = vec![];
+    for part in s.split(',') {
+        let part = part.trim();
+        if part.starts_with("U+") && part.len() > 2 {
+            let range = &part[2 ..];
+            let (first, last) = match range.find('-') {
+                Some(i) => (range[.. i].to_owned(), range[i + 1 ..].to_owned()),
+                None => (range.replace('?', "0"), range.replace('?', "F")),
+            };
+            let first = u32::from_str_radix(&first, 16).ok().and_then(std::char::from_u32).ok_or_else(err)?;
+            let last = u32::from_str_radix(&last, 16).ok().and_then(std::char::from_u32).ok_or_else(err)?;
+            list.push((first, last));
+        } else {
+            // a single character. the comma itself can't be listed.
+            let mut chars = part.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => list.push((c, c)),
+                _ => {}
+            }
+        }
+    }
+    Ok(list)
+}
+
+#[test]
+fn test_font_family() {
+    use FontFamily::*;
+    assert_eq!(
+        FontFamilyList::parse("'Noto Sans',  DejaVu   Serif, monospace").unwrap(),
+        FontFamilyList(vec![Named("Noto Sans".into()), Named("DejaVu Serif".into()), Generic(GenericFamily::Monospace)])
+    );
+    assert_eq!(
+        FontFamilyList::parse("\"serif\"").unwrap(),
+        FontFamilyList(vec![Named("serif".into())])
+    );
+    assert!(FontFamilyList::parse("Arial,,serif").is_err());
+}
+
+#[test]
+fn test_font_shorthand() {
+    let font = FontShorthand::parse("italic bold 12px/30px Georgia, serif").unwrap();
+    assert_eq!(font.style, FontStyle::Italic);
+    assert_eq!(font.weight, FontWeight::Absolute(700));
+    assert_eq!(font.family.0.len(), 2);
+    assert!(FontShorthand::parse("bold serif").is_err());
+    let font = FontShorthand::parse("condensed 80% serif").unwrap();
+    assert_eq!(font.stretch, FontStretch(75.));
+    assert_eq!(font.size.0, Length::new(80., LengthUnit::Percent));
+    let font = FontShorthand::parse("bold medium serif").unwrap();
+    assert_eq!(font.size.0, Length::new(16., LengthUnit::Px));
+    assert_eq!(FontShorthand::parse("larger serif").unwrap().size.0, Length::new(120., LengthUnit::Percent));
+    assert!(FontShorthand::is_system_font("caption"));
+}
+
+#[test]
+fn test_unicode_list() {
+    assert_eq!(unicode_list("a, U+0600-06FF, U+00??").unwrap(), vec![('a', 'a'), ('\u{600}', '\u{6ff}'), ('\0', '\u{ff}')]);
+    assert!(unicode_list("U+XYZ").is_err());
+}
@@ -31,6 +31,7 @@ pub struct TagSymbol {
     pub attrs: Attrs,
     pub id: Option<String>,
     pub view_box: Option<Rect>,
+    pub preserve_aspect_ratio: PreserveAspectRatio,
 }
 impl Tag for TagSymbol {
     fn id(&self) -> Option<&str> {
@@ -46,8 +47,9 @@ impl ParseNode for TagSymbol {
         let items = parse_node_list(node.children())?;
         let id = node.attribute("id").map(|s| s.into());
         let view_box = node.attribute("viewBox").map(Rect::parse).transpose()?;
+        let preserve_aspect_ratio = parse_attr_or(node, "preserveAspectRatio", PreserveAspectRatio::default())?;
 
-        Ok(TagSymbol { items, attrs, id,view_box })
+        Ok(TagSymbol { items, attrs, id, view_box, preserve_aspect_ratio })
     }
 }
 
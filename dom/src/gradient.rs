@@ -3,7 +3,6 @@ use pathfinder_content::gradient::{Gradient};
 use pathfinder_color::{ColorU};
 use pathfinder_geometry::line_segment::LineSegment2F;
 use pathfinder_simd::default::F32x2;
-use svgtypes::Color;
 
 #[derive(Debug)]
 pub struct TagLinearGradient {
@@ -13,6 +12,7 @@ pub struct TagLinearGradient {
     pub stops: Vec<TagStop>,
     pub id: Option<String>,
     pub href: Option<String>,
+    pub color_interpolation: Option<ColorInterpolation>,
 }
 
 #[derive(Debug)]
@@ -24,6 +24,7 @@ pub struct TagRadialGradient {
     pub stops: Vec<TagStop>,
     pub id: Option<String>,
     pub href: Option<String>,
+    pub color_interpolation: Option<ColorInterpolation>,
 }
 
 #[derive(Debug)]
@@ -51,6 +52,7 @@ impl ParseNode for TagLinearGradient {
             var x2: Option<LengthX>,
             var y2: Option<LengthY>,
             var id,
+            var color_interpolation ("color-interpolation"): Option<ColorInterpolation> => inherit(ColorInterpolation::parse),
         });
         let gradient_transform = node.attribute("gradientTransform").map(transform_list).transpose()?;
         let href = href(node);
@@ -69,7 +71,8 @@ impl ParseNode for TagLinearGradient {
             gradient_transform,
             stops,
             id,
-            href
+            href,
+            color_interpolation,
         })
     }
 }
@@ -82,6 +85,7 @@ impl ParseNode for TagRadialGradient {
             var fy: Option<LengthY>,
             var r: Option<Length>,
             var id,
+            var color_interpolation ("color-interpolation"): Option<ColorInterpolation> => inherit(ColorInterpolation::parse),
         });
         let gradient_transform = node.attribute("gradientTransform").map(transform_list).transpose()?;
         let href = href(node);
@@ -102,6 +106,7 @@ impl ParseNode for TagRadialGradient {
             stops,
             id,
             href,
+            color_interpolation,
         })
     }
 }
@@ -115,7 +120,7 @@ impl TagStop {
         match key {
             "offset" => self.offset = number_or_percent(val)?,
             "stop-opacity" => self.opacity = opacity(val)?,
-            "stop-color" => self.color = Color::from_str(val)?,
+            "stop-color" => self.color = Color::parse(val)?,
             "style" => {
                 for (key, val) in style_list(val) {
                     self.apply(key, val)?;
@@ -127,9 +132,7 @@ impl TagStop {
     }
 
     pub fn color_u(&self, opacity: f32) -> ColorU {
-        let Color { red, green, blue } = self.color;
-        let alpha = (opacity * self.opacity * 255.) as u8;
-        ColorU::new(red, green, blue, alpha)
+        self.color.color_u(opacity * self.opacity)
     }
 }
 impl ParseNode for TagStop {
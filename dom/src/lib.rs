@@ -15,12 +15,15 @@ pub mod prelude {
         ellipse::*,
         error::*,
         filter::*,
+        font::*,
         g::*,
         gradient::*,
+        mask::*,
         paint::*,
         path::*,
         polygon::*,
         rect::*,
+        style::*,
         svg::*,
         text::*,
         util::*,
@@ -42,13 +45,16 @@ mod attrs;
 mod ellipse;
 mod error;
 mod filter;
+mod font;
 mod g;
 mod gradient;
+mod mask;
 mod paint;
 mod parser;
 mod path;
 mod polygon;
 mod rect;
+mod style;
 mod svg;
 mod text;
 mod util;
@@ -107,6 +113,7 @@ items!(
         "linearGradient" => LinearGradient(TagLinearGradient),
         "radialGradient" => RadialGradient(TagRadialGradient),
         "clipPath" => ClipPath(TagClipPath),
+        "mask" => Mask(TagMask),
         "filter" => Filter(TagFilter),
         "svg" => Svg(TagSvg),
         "use" => Use(TagUse),
@@ -114,6 +121,10 @@ items!(
         "text" => Text(TagText),
         "tspan" => TSpan(TagTSpan),
         "tref" => TRef(TagTRef),
+        "textArea" => TextArea(TagTextArea),
+        "tbreak" => TBreak(TagTBreak),
+        "style" => Style(TagStyle),
+        "font" => Font(TagFont),
     }
     {
         String(String),
@@ -154,41 +165,18 @@ fn link(ids: &mut ItemCollection, item: &Arc<Item>) {
     }
 }
 
-fn parse_node(node: &Node, first: bool, last: bool) -> Result<Option<Item>, Error> {
+fn parse_node(node: &Node, first: bool, last: bool, white_space: WhiteSpace) -> Result<Option<Item>, Error> {
     match node.node_type() {
         NodeType::Element => parse_element(node),
-        NodeType::Text => parse_text(node, first, last),
+        NodeType::Text => parse_text(node, first, last, white_space),
         _ => Ok(None)
     }
 }
 
-fn parse_text(node: &Node, first: bool, last: bool) -> Result<Option<Item>, Error> {
+/// `white_space` is the mode of the parent element
+fn parse_text(node: &Node, first: bool, last: bool, white_space: WhiteSpace) -> Result<Option<Item>, Error> {
     Ok(node.text().and_then(|s| {
-        let mut last_is_space = first;
-        let mut processed: String = s.chars()
-        .filter_map(|c| {
-            if last_is_space {
-                match c {
-                    '\n' | '\t' | ' ' => None,
-                    _ => {
-                        last_is_space = false;
-                        Some(c)
-                    }
-                }
-            } else {
-                match c {
-                    '\n' => None,
-                    '\t' | ' ' => {
-                        last_is_space = true;
-                        Some(' ')
-                    }
-                    c => Some(c)
-                }
-            }
-        }).collect();
-        if last && last_is_space && processed.len() > 0 {
-            processed.pop();
-        }
+        let processed = process_white_space(s, first, last, white_space);
         if processed.len() > 0 {
             Some(Item::String(processed))
         } else {
@@ -197,12 +185,117 @@ fn parse_text(node: &Node, first: bool, last: bool) -> Result<Option<Item>, Erro
     }))
 }
 
+/// `first` and `last` tell whether the text is at the start or end of its parent,
+/// where collapsible spaces are removed
+fn process_white_space(s: &str, first: bool, last: bool, white_space: WhiteSpace) -> String {
+    let newline = match white_space {
+        // tabs and newlines become spaces, as there are no tab stops or line breaks
+        WhiteSpace::Pre | WhiteSpace::PreWrap => return s.chars().map(|c| match c {
+            '\n' | '\t' => ' ',
+            c => c
+        }).collect(),
+        WhiteSpace::Normal | WhiteSpace::PreLine => Some(' '),
+        // `xml:space="default"` removes newlines
+        WhiteSpace::Default => None,
+    };
+
+    let mut last_is_space = first;
+    let mut processed: String = s.chars()
+    .filter_map(|c| {
+        let c = match c {
+            '\n' => newline?,
+            '\t' => ' ',
+            c => c
+        };
+        match c {
+            ' ' if last_is_space => None,
+            ' ' => {
+                last_is_space = true;
+                Some(' ')
+            }
+            c => {
+                last_is_space = false;
+                Some(c)
+            }
+        }
+    }).collect();
+    if last && last_is_space && processed.len() > 0 {
+        processed.pop();
+    }
+    processed
+}
+
+#[test]
+fn test_white_space() {
+    assert_eq!(process_white_space("  a \n\tb  ", true, true, WhiteSpace::Default), "a b");
+    assert_eq!(process_white_space("a\nb", false, false, WhiteSpace::Default), "ab");
+    assert_eq!(process_white_space("foo\nbar", true, true, WhiteSpace::Normal), "foo bar");
+    assert_eq!(process_white_space("a \n b", false, false, WhiteSpace::Normal), "a b");
+    assert_eq!(process_white_space("a\nb", false, false, WhiteSpace::PreLine), "a b");
+    assert_eq!(process_white_space(" a\t\n b ", true, true, WhiteSpace::Pre), " a   b ");
+}
+
+#[test]
+fn test_white_space_property() {
+    let text = |attrs: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg"><text id="t" {}>foo
+bar</text></svg>"#, attrs)).unwrap();
+        match **svg.get_item("t").unwrap() {
+            Item::Text(ref tag) => match *tag.items[0] {
+                Item::String(ref s) => s.clone(),
+                _ => unreachable!()
+            }
+            _ => unreachable!()
+        }
+    };
+    assert_eq!(text(""), "foobar");
+    assert_eq!(text(r#"xml:space="default""#), "foobar");
+    assert_eq!(text(r#"style="white-space: normal""#), "foo bar");
+    assert_eq!(text(r#"white-space="nowrap" xml:space="preserve""#), "foo bar");
+}
+
+#[test]
+fn test_style_precedence() {
+    let inline_size = |attrs: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg"><text id="t" {}>a</text></svg>"#, attrs)).unwrap();
+        match **svg.get_item("t").unwrap() {
+            Item::Text(ref tag) => tag.inline_size.map(|l| l.0.num),
+            _ => unreachable!()
+        }
+    };
+    assert_eq!(inline_size(r#"style="inline-size: 100px""#), Some(100.));
+    assert_eq!(inline_size(r#"style="inline-size: 100px" inline-size="50""#), Some(100.));
+    assert_eq!(inline_size(r#"inline-size="50" style="inline-size: auto""#), None);
+}
+
+#[test]
+fn test_font_shorthand_precedence() {
+    let font = |attrs: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg"><rect id="r" {}/></svg>"#, attrs)).unwrap();
+        match **svg.get_item("r").unwrap() {
+            Item::Rect(ref rect) => (rect.attrs.font_size.value.map(|size| size.0.num), rect.attrs.font_weight),
+            _ => unreachable!()
+        }
+    };
+    let bold = Some(FontWeight::Absolute(700));
+    let normal = Some(FontWeight::Absolute(400));
+    // the shorthand resets the longhands declared before it
+    assert_eq!(font(r#"style="font-size: 10px; font-weight: bold; font: 12px serif""#), (Some(12.), normal));
+    assert_eq!(font(r#"style="font: 12px serif; font-weight: bold""#), (Some(12.), bold));
+    // the style attribute wins over the attributes
+    assert_eq!(font(r#"font-weight="bold" style="font: 12px serif""#), (Some(12.), normal));
+    assert_eq!(font(r#"font="12px serif" style="font-weight: bold""#), (Some(12.), bold));
+    assert_eq!(font(r#"style="font-weight: bold" font="12px serif""#), (Some(12.), bold));
+    // system fonts are ignored
+    assert_eq!(font(r#"font="bold 12px serif" style="font: caption""#), (Some(12.), bold));
+}
+
 fn parse_node_list<'a, 'i: 'a>(nodes: impl Iterator<Item=Node<'a, 'i>>) -> Result<Vec<Arc<Item>>, Error> {
     let mut items = Vec::new();
     for (first, last, node) in first_or_last_node(nodes) {
         match node.node_type() {
             NodeType::Element => {
-                if let Some(item) = parse_node(&node, first, last)? {
+                if let Some(item) = parse_node(&node, first, last, WhiteSpace::Default)? {
                     items.push(Arc::new(item));
                 }
             }
@@ -71,27 +71,31 @@ macro_rules! parse {
         $(
             let mut $var $(: $ty)? = parse!( @default $(= $default)* );
         )*
-        $( let mut $items = Vec::new(); )?
+        $(
+            let mut $items = Vec::new();
+            let white_space = crate::text::white_space(&$node);
+        )?
         for attribute in $node.attributes() {
             let val = attribute.value();
             match attribute.name() {
                 $( parse!(@name $var $( ($name) )?) => $var = parse!(@parse val $(,$parser)? )?, )*
-                "style" => {
-                    for (key, val) in $crate::util::style_list(val) {
-                        match key {
-                            $( parse!(@name $var $( ($name) )?) => $var = parse!(@parse val $(,$parser)? )?, )*
-                            _ => {}
-                        }
-                    }
-                }
                 _ => {}
             }
         }
+        // declarations in the style attribute take precedence over the attributes
+        if let Some(style) = $node.attribute("style") {
+            for (key, val) in $crate::util::style_list(style) {
+                match key {
+                    $( parse!(@name $var $( ($name) )?) => $var = parse!(@parse val $(,$parser)? )?, )*
+                    _ => {}
+                }
+            }
+        }
         #[allow(unused)]
         for (first, last, n) in crate::first_or_last_node($node.children()) {
             if n.is_element() {
                 match n.tag_name().name() {
-                    "animate" | "animateColor" => match n.attribute("attributeName").unwrap() {
+                    "animate" | "animateColor" | "set" => match n.attribute("attributeName").unwrap() {
                         $( parse!(@name $var2 $( ($name2) )?) => $var2.parse_animate_node(&n)?, )*
                         _ => continue,
                     }
@@ -99,7 +103,7 @@ macro_rules! parse {
                 }
             }
             $(
-                if let Some(item) = parse_node(&n, first, last)? {
+                if let Some(item) = parse_node(&n, first, last, white_space)? {
                     $items.push(Arc::new(item));
                 }
             )?
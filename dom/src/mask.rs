@@ -0,0 +1,63 @@
+use crate::prelude::*;
+use crate::parse_node_list;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct TagMask {
+    pub id: Option<String>,
+    pub items: Vec<Arc<Item>>,
+    pub attrs: Attrs,
+    pub mask_units: Units,
+    pub mask_content_units: Units,
+    pub mask_type: MaskType,
+    pub x: Option<LengthX>,
+    pub y: Option<LengthY>,
+    pub width: Option<LengthX>,
+    pub height: Option<LengthY>,
+}
+impl Tag for TagMask {
+    fn id(&self) -> Option<&str> {
+        self.id.as_ref().map(|s| s.as_str())
+    }
+    fn children(&self) -> &[Arc<Item>] {
+        &self.items
+    }
+}
+impl ParseNode for TagMask {
+    fn parse_node(node: &Node) -> Result<TagMask, Error> {
+        parse!(node => {
+            var id,
+            var x: Option<LengthX>,
+            var y: Option<LengthY>,
+            var width: Option<LengthX>,
+            var height: Option<LengthY>,
+            var mask_units ("maskUnits"): Units = Units::ObjectBoundingBox,
+            var mask_content_units ("maskContentUnits"): Units = Units::UserSpaceOnUse,
+            var mask_type ("mask-type"): MaskType = MaskType::Luminance,
+        });
+        let attrs = Attrs::parse(node)?;
+        let items = parse_node_list(node.children())?;
+
+        Ok(TagMask {
+            id, items, attrs,
+            mask_units, mask_content_units, mask_type,
+            x, y, width, height,
+        })
+    }
+}
+
+/// whether the luminance or the alpha channel of the mask content is used
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MaskType {
+    Luminance,
+    Alpha,
+}
+impl Parse for MaskType {
+    fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "luminance" => Ok(MaskType::Luminance),
+            "alpha" => Ok(MaskType::Alpha),
+            _ => Err(Error::InvalidAttributeValue(s.into()))
+        }
+    }
+}
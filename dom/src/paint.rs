@@ -6,25 +6,38 @@ use pathfinder_color::{ColorF, ColorU};
 pub struct Color {
     pub red: f32,
     pub green: f32,
-    pub blue: f32
+    pub blue: f32,
+    pub alpha: f32,
 }
 impl Color {
     pub fn from_srgb_u8(r: u8, g: u8, b: u8) -> Color {
         Color {
             red: r as f32 * (1.0/255.),
             green: g as f32 * (1.0/255.),
-            blue: b as f32 * (1.0/255.)
+            blue: b as f32 * (1.0/255.),
+            alpha: 1.0,
         }
     }
+    pub fn from_srgba(red: f32, green: f32, blue: f32, alpha: f32) -> Color {
+        Color { red, green, blue, alpha }
+    }
     pub fn black() -> Color {
         Color {
             red: 0.,
             green: 0.,
-            blue: 0.
+            blue: 0.,
+            alpha: 1.0,
+        }
+    }
+    pub fn transparent() -> Color {
+        Color {
+            alpha: 0.0,
+            .. Color::black()
         }
     }
+    /// the alpha of the color is multiplied with the given `alpha`
     pub fn color_f(&self, alpha: f32) -> ColorF {
-        ColorF::new(self.red, self.green, self.blue, alpha)
+        ColorF::new(self.red, self.green, self.blue, self.alpha * alpha)
     }
     pub fn color_u(&self, alpha: f32) -> ColorU {
         self.color_f(alpha).to_u8()
@@ -44,7 +57,10 @@ fn test_color() {
 pub enum Paint {
     None,
     Color(Color),
-    Ref(String),
+    /// the value of the `color` property
+    CurrentColor,
+    /// paint server and the fallback used when it can't be resolved
+    Ref(String, Option<Box<Paint>>),
 }
 impl Paint {
     pub fn is_none(&self) -> bool {
@@ -68,4 +84,6 @@ impl Parse for Paint {
 #[test]
 fn test_paint() {
     assert_eq!(Paint::parse("#aabbcc").unwrap(), Paint::Color(Color::from_srgb_u8(0xaa, 0xbb, 0xcc)));
+    assert_eq!(Paint::parse("currentColor").unwrap(), Paint::CurrentColor);
+    assert_eq!(Paint::parse("url(#missing) red").unwrap(), Paint::Ref("missing".into(), Some(Box::new(Paint::Color(Color::from_srgb_u8(255, 0, 0))))));
 }
\ No newline at end of file
@@ -1,13 +1,14 @@
 use nom::{
     IResult,
     bytes::complete::{tag, take_while_m_n},
-    character::complete::{alpha1, space0, digit1},
-    combinator::{map, map_res},
-    sequence::tuple,
+    character::complete::{alpha1, space0, space1},
+    combinator::{map, opt},
+    number::complete::float,
+    sequence::{tuple, preceded},
     branch::alt,
-    Err::Failure
 };
 use crate::prelude::*;
+use itertools::Itertools;
 
 fn is_hex_digit(c: char) -> bool {
     match c {
@@ -16,59 +17,34 @@ fn is_hex_digit(c: char) -> bool {
     }
 }
 
-fn from_hex(input: &str) -> Result<u8, std::num::ParseIntError> {
-    u8::from_str_radix(input, 16)
-}
-fn from_decimal(input: &str) -> Result<u8, std::num::ParseIntError> {
-    u8::from_str(input)
-}
-fn hex_byte(input: &str) -> IResult<&str, u8, ()> {
-    map_res(
-      take_while_m_n(2, 2, is_hex_digit),
-      from_hex
-    )(input)
+fn hex_digits(s: &str) -> impl Iterator<Item=u8> + '_ {
+    s.chars().map(|c| c.to_digit(16).unwrap() as u8)
 }
 
-fn hex_nibble(input: &str) -> IResult<&str, u8, ()> {
-    map(
-        map_res(
-            take_while_m_n(1, 1, is_hex_digit),
-            from_hex
-        ),
-        |n| 16 * n
-    )(input)
-}
-fn integer(input: &str) -> IResult<&str, u8, ()> {
-    map_res(
-        digit1,
-        from_decimal
-    )(input)
-}
-fn percent(i: &str) -> IResult<&str, u8, ()> {
-    let (i, (pc, _)) = tuple((integer, tag("%")))(i)?;
-    if pc > 100 {
-        Err(Failure(()))
-    } else {
-        let val = (((pc as u16) * 255) / 100) as u8;
-        Ok((i, val))
-    }
-}
-fn comma(input: &str) -> IResult<&str, (), ()> {
-    map(
-        tag(","),
-        |_| ()
-    )(input)
-}
+// "#" followed by 3, 4, 6 or 8 hex digits
 fn hex_color(i: &str) -> IResult<&str, Color, ()> {
-    let (i, (_, r, g, b)) = tuple((tag("#"), hex_byte, hex_byte, hex_byte))(i)?;
-    Ok((i, Color::from_srgb_u8(r, g, b)))
-}
-fn short_hex_color(i: &str) -> IResult<&str, Color, ()> {
-    let (i, (_, r, g, b)) = tuple((tag("#"), hex_nibble, hex_nibble, hex_nibble))(i)?;
-    Ok((i, Color::from_srgb_u8(r, g, b)))
+    let (i, (_, digits)) = tuple((tag("#"), take_while_m_n(3, 8, is_hex_digit)))(i)?;
+    let mut d = hex_digits(digits);
+    let mut nibble = || d.next().unwrap() * 17;
+    let (r, g, b, a) = match digits.len() {
+        3 => (nibble(), nibble(), nibble(), 255),
+        4 => (nibble(), nibble(), nibble(), nibble()),
+        6 | 8 => {
+            let bytes: Vec<u8> = hex_digits(digits).tuples().map(|(hi, lo)| 16 * hi + lo).collect();
+            (bytes[0], bytes[1], bytes[2], bytes.get(3).cloned().unwrap_or(255))
+        }
+        _ => return Err(nom::Err::Error(()))
+    };
+    Ok((i, Color {
+        alpha: a as f32 * (1.0 / 255.),
+        .. Color::from_srgb_u8(r, g, b)
+    }))
 }
 fn color_name(i: &str) -> IResult<&str, Color, ()> {
     let (i, name) = alpha1(i)?;
+    if name == "transparent" {
+        return Ok((i, Color::transparent()));
+    }
     match COLOR_NAMES.binary_search_by_key(&name, |&(name, _)| name) {
         Ok(idx) => {
             let (_, (r, g, b)) = COLOR_NAMES[idx];
@@ -77,48 +53,128 @@ fn color_name(i: &str) -> IResult<&str, Color, ()> {
         _ => Err(nom::Err::Error(()))
     }
 }
-// "rgb(" wsp* integer comma integer comma integer wsp* ")"
-fn rgb_color(i: &str) -> IResult<&str, Color, ()> {
-    let (i, _) = tag("rgb(")(i)?;
-    let (i, _) = space0(i)?;
-    let (i, r) = integer(i)?;
-    let (i, _) = comma(i)?;
-    let (i, g) = integer(i)?;
-    let (i, _) = comma(i)?;
-    let (i, b) = integer(i)?;
-    let (i, _) = space0(i)?;
-    let (i, _) = tag(")")(i)?;
-    Ok((i, Color::from_srgb_u8(r, g, b)))
+
+#[derive(Copy, Clone)]
+enum Component {
+    Number(f32),
+    Percent(f32),
+}
+impl Component {
+    // number in 0 ..= `max` or percentage, mapped to 0 ..= 1
+    fn unit(self, max: f32) -> f32 {
+        let val = match self {
+            Component::Number(n) => n / max,
+            Component::Percent(p) => p * 0.01,
+        };
+        val.max(0.0).min(1.0)
+    }
+}
+fn component(i: &str) -> IResult<&str, Component, ()> {
+    let (i, val) = float(i)?;
+    let (i, percent) = opt(tag("%"))(i)?;
+    Ok((i, match percent {
+        Some(_) => Component::Percent(val),
+        None => Component::Number(val)
+    }))
 }
 
-// "rgb(" wsp* integer "%" comma integer "%" comma integer "%" wsp* ")"
-fn rgb_percent_color(i: &str) -> IResult<&str, Color, ()> {
-    let (i, _) = tag("rgb(")(i)?;
+// hue in degrees
+fn hue(i: &str) -> IResult<&str, f32, ()> {
+    let (i, val) = float(i)?;
+    let (i, unit) = opt(alt((tag("deg"), tag("grad"), tag("rad"), tag("turn"))))(i)?;
+    let deg = match unit {
+        Some("grad") => val * 0.9,
+        Some("rad") => val.to_degrees(),
+        Some("turn") => val * 360.,
+        _ => val
+    };
+    Ok((i, deg))
+}
+
+// returns true for the legacy, comma separated syntax
+fn first_sep(i: &str) -> IResult<&str, bool, ()> {
+    alt((
+        map(tuple((space0, tag(","), space0)), |_| true),
+        map(space1, |_| false)
+    ))(i)
+}
+fn sep(legacy: bool) -> impl Fn(&str) -> IResult<&str, (), ()> {
+    move |i| match legacy {
+        true => map(tuple((space0, tag(","), space0)), |_| ())(i),
+        false => map(space1, |_| ())(i),
+    }
+}
+fn alpha_sep(legacy: bool) -> impl Fn(&str) -> IResult<&str, (), ()> {
+    move |i| map(tuple((space0, tag(if legacy { "," } else { "/" }), space0)), |_| ())(i)
+}
+
+// (first, second, third, alpha)
+fn color_args<'i, T>(i: &'i str, first: impl Fn(&'i str) -> IResult<&'i str, T, ()>) -> IResult<&'i str, (T, Component, Component, f32), ()> {
     let (i, _) = space0(i)?;
-    let (i, r) = percent(i)?;
-    let (i, _) = comma(i)?;
-    let (i, g) = percent(i)?;
-    let (i, _) = comma(i)?;
-    let (i, b) = percent(i)?;
+    let (i, a) = first(i)?;
+    let (i, legacy) = first_sep(i)?;
+    let (i, b) = component(i)?;
+    let (i, _) = sep(legacy)(i)?;
+    let (i, c) = component(i)?;
+    let (i, alpha) = opt(preceded(alpha_sep(legacy), component))(i)?;
     let (i, _) = space0(i)?;
     let (i, _) = tag(")")(i)?;
-    Ok((i, Color::from_srgb_u8(r, g, b)))
+    Ok((i, (a, b, c, alpha.map(|a| a.unit(1.0)).unwrap_or(1.0))))
+}
+
+// rgb() and rgba() in the legacy comma separated and the CSS Color 4 syntax
+fn rgb_color(i: &str) -> IResult<&str, Color, ()> {
+    let (i, _) = alt((tag("rgba("), tag("rgb(")))(i)?;
+    let (i, (r, g, b, alpha)) = color_args(i, component)?;
+    Ok((i, Color::from_srgba(r.unit(255.), g.unit(255.), b.unit(255.), alpha)))
+}
+
+// hsl() and hsla()
+fn hsl_color(i: &str) -> IResult<&str, Color, ()> {
+    let (i, _) = alt((tag("hsla("), tag("hsl(")))(i)?;
+    let (i, (h, s, l, alpha)) = color_args(i, hue)?;
+    let (r, g, b) = hsl_to_rgb(h, s.unit(100.), l.unit(100.));
+    Ok((i, Color::from_srgba(r, g, b, alpha)))
+}
+
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> (f32, f32, f32) {
+    let hue = hue.rem_euclid(360.) / 30.;
+    let a = saturation * lightness.min(1.0 - lightness);
+    let f = |n: f32| {
+        let k = (n + hue) % 12.;
+        lightness - a * (k - 3.).min(9. - k).min(1.).max(-1.)
+    };
+    (f(0.), f(8.), f(4.))
 }
 
 pub fn color(i: &str) -> IResult<&str, Color, ()> {
     alt((
         hex_color,
-        short_hex_color,
         rgb_color,
-        rgb_percent_color,
+        hsl_color,
         color_name
     ))(i)
 }
 
+#[cfg(test)]
+fn rgba_u8(i: &str) -> [u8; 4] {
+    let c = color(i).unwrap().1;
+    let byte = |v: f32| (v * 255.).round() as u8;
+    [byte(c.red), byte(c.green), byte(c.blue), byte(c.alpha)]
+}
+
 #[test]
 fn test_color() {
     assert!(color("rgb(1,2,3)").is_ok());
     assert_eq!(color("#012345").unwrap().1, Color::from_srgb_u8(0x01, 0x23, 0x45));
+    assert_eq!(rgba_u8("#fff"), [255, 255, 255, 255]);
+    assert_eq!(rgba_u8("#ff000080"), [255, 0, 0, 128]);
+    assert_eq!(rgba_u8("rgba(255, 0, 0, 0.5)"), [255, 0, 0, 128]);
+    assert_eq!(rgba_u8("rgb(255 0 0 / 50%)"), [255, 0, 0, 128]);
+    assert_eq!(rgba_u8("rgb(100%,0%,0%)"), [255, 0, 0, 255]);
+    assert_eq!(rgba_u8("hsl(120, 100%, 50%)"), [0, 255, 0, 255]);
+    assert_eq!(rgba_u8("hsla(240deg 100% 50% / 0.25)"), [0, 0, 255, 64]);
+    assert_eq!(rgba_u8("transparent"), [0, 0, 0, 0]);
 }
 
 static COLOR_NAMES: &[(&str, (u8, u8, u8))] = &[
@@ -50,13 +50,30 @@ pub fn parse_color(s: &str) -> Result<Color, Error> {
         }
     }
 }
+fn current_color(i: &str) -> R<&str> {
+    alt((tag("currentColor"), tag("currentcolor")))(i)
+}
+
+// fallback after a paint server reference
+fn paint_fallback(i: &str) -> R<Paint> {
+    alt((
+        map(tag("none"), |_| Paint::None),
+        map(current_color, |_| Paint::CurrentColor),
+        map(color::color, Paint::Color),
+    ))(i)
+}
+
 pub fn parse_paint(s: &str) -> Result<Paint, Error> {
     match alt((
         map(tag("none"), |_| Paint::None),
         map(tag("transparent"), |_| Paint::None),
-        map(func_iri, |s| Paint::Ref(s.into())),
+        map(current_color, |_| Paint::CurrentColor),
+        map(
+            tuple((func_iri, opt(preceded(space1, paint_fallback)))),
+            |(s, fallback)| Paint::Ref(s.into(), fallback.map(Box::new))
+        ),
         map(color::color, Paint::Color),
-    ))(s) {
+    ))(s.trim()) {
         Ok((_, paint)) => Ok(paint),
         Err(e) => {
             debug!("parse_paint({:?}): {:?}", s, e);
@@ -67,7 +84,8 @@ pub fn parse_paint(s: &str) -> Result<Paint, Error> {
 
 #[test]
 fn test_paint() {
-    assert_eq!(parse_paint("url(#radialGradient862)").unwrap(), Paint::Ref("radialGradient862".into()));
+    assert_eq!(parse_paint("url(#radialGradient862)").unwrap(), Paint::Ref("radialGradient862".into(), None));
+    assert_eq!(parse_paint("url(#a) none").unwrap(), Paint::Ref("a".into(), Some(Box::new(Paint::None))));
 }
 
 fn list_sep(i: &str) -> IResult<&str, &str, ()> {
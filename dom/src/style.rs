@@ -0,0 +1,261 @@
+use crate::prelude::*;
+use std::fmt;
+use std::sync::Arc;
+
+/// a `<style>` element. only the `@font-face` rules are used, the other rules are ignored.
+#[derive(Debug)]
+pub struct TagStyle {
+    pub font_faces: Vec<Arc<FontFace>>,
+}
+impl Tag for TagStyle {}
+impl ParseNode for TagStyle {
+    fn parse_node(node: &Node) -> Result<TagStyle, Error> {
+        let css: String = node.children().filter_map(|n| n.text()).collect();
+        let font_faces = font_face_rules(&strip_comments(&css))
+            .filter_map(FontFace::parse_block)
+            .map(Arc::new)
+            .collect();
+        Ok(TagStyle { font_faces })
+    }
+}
+
+/// a `@font-face` rule. descriptors that are missing or invalid are `None`.
+#[derive(Debug)]
+pub struct FontFace {
+    pub family: String,
+    pub weight: Option<FontWeight>,
+    pub style: Option<FontStyle>,
+    pub stretch: Option<FontStretch>,
+    /// the sources in order of preference
+    pub sources: Vec<FontSource>,
+}
+impl FontFace {
+    /// the content of the braces, `None` without a family or source
+    fn parse_block(block: &str) -> Option<FontFace> {
+        let mut family = None;
+        let mut weight = None;
+        let mut style = None;
+        let mut stretch = None;
+        let mut sources = vec![];
+        for (key, val) in declarations(block) {
+            // ranges of variable fonts are reduced to their start
+            let first = val.split_whitespace().next().unwrap_or_default();
+            match key {
+                "font-family" => family = match FontFamilyList::parse(val) {
+                    Ok(FontFamilyList(list)) => match list.into_iter().next() {
+                        Some(FontFamily::Named(name)) => Some(name),
+                        _ => None
+                    }
+                    Err(_) => None
+                },
+                "font-weight" => weight = FontWeight::parse(first).ok(),
+                "font-style" => style = FontStyle::parse(val).ok(),
+                "font-stretch" => stretch = FontStretch::parse(first).ok(),
+                "src" => sources = split_top_level(val, ',').filter_map(FontSource::parse).collect(),
+                _ => {}
+            }
+        }
+        if sources.len() == 0 {
+            return None;
+        }
+        Some(FontFace { family: family?, weight, style, stretch, sources })
+    }
+}
+
+#[derive(Clone)]
+pub enum FontSource {
+    /// the decoded content of a `data:` URI
+    Data { mime: String, data: Arc<[u8]> },
+    /// any other URL, which is not loaded
+    Url(String),
+}
+impl fmt::Debug for FontSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FontSource::Data { ref mime, ref data } => write!(f, "Data({}, {} bytes)", mime, data.len()),
+            FontSource::Url(ref url) => write!(f, "Url({})", url),
+        }
+    }
+}
+impl FontSource {
+    /// one entry of `src`. `local()` sources are skipped, the family falls back to the system fonts anyway.
+    fn parse(s: &str) -> Option<FontSource> {
+        let s = s.trim();
+        if !s.starts_with("url(") {
+            return None;
+        }
+        // format() hints follow the closing parenthesis
+        let inner = s[4 ..].trim_start();
+        let url = match inner.chars().next() {
+            Some(q) if q == '"' || q == '\'' => &inner[1 .. 1 + inner[1 ..].find(q)?],
+            _ => inner[.. inner.find(')')?].trim_end(),
+        };
+        if url.starts_with("data:") {
+            let (mime, data) = data_uri(url)?;
+            Some(FontSource::Data { mime, data: data.into() })
+        } else {
+            Some(FontSource::Url(url.into()))
+        }
+    }
+}
+
+/// removes `/* ... */`
+fn strip_comments(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[.. start]);
+        rest = match rest[start + 2 ..].find("*/") {
+            Some(end) => &rest[start + 2 + end + 2 ..],
+            None => ""
+        };
+    }
+    out.push_str(rest);
+    out
+}
+
+/// the content of the blocks of the `@font-face` rules
+fn font_face_rules(css: &str) -> impl Iterator<Item=&str> {
+    let mut rest = css;
+    std::iter::from_fn(move || {
+        let start = rest.find("@font-face")?;
+        let open = start + rest[start ..].find('{')?;
+        let close = open + rest[open ..].find('}')?;
+        let block = &rest[open + 1 .. close];
+        rest = &rest[close + 1 ..];
+        Some(block)
+    })
+}
+
+/// `s` split at `sep` outside of quotes and parentheses
+fn split_top_level(s: &str, sep: char) -> impl Iterator<Item=&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, c) if c == sep && depth == 0 => {
+                parts.push(&s[start .. i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start ..]);
+    parts.into_iter()
+}
+
+/// like `style_list`, but the values may contain `;` in quotes or `url()`
+fn declarations(block: &str) -> impl Iterator<Item=(&str, &str)> {
+    split_top_level(block, ';').filter_map(|decl| {
+        let colon = decl.find(':')?;
+        Some((decl[.. colon].trim(), decl[colon + 1 ..].trim()))
+    })
+}
+
+/// mime type and data of a `data:` URI
+pub fn data_uri(uri: &str) -> Option<(String, Vec<u8>)> {
+    if !uri.starts_with("data:") {
+        return None;
+    }
+    let comma = uri.find(',')?;
+    let (header, data) = (&uri[5 .. comma], &uri[comma + 1 ..]);
+    let mut params = header.split(';');
+    let mime = params.next().unwrap_or_default().trim().to_lowercase();
+    let data = match params.any(|p| p.trim() == "base64") {
+        true => base64(data)?,
+        false => percent_decode(data),
+    };
+    Some((mime, data))
+}
+
+fn base64(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for b in s.bytes() {
+        let v = match b {
+            b'A' ..= b'Z' => b - b'A',
+            b'a' ..= b'z' => b - b'a' + 26,
+            b'0' ..= b'9' => b - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            // line breaks inside of the attribute value
+            b if b.is_ascii_whitespace() => continue,
+            _ => return None
+        };
+        acc = acc << 6 | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+fn percent_decode(s: &str) -> Vec<u8> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1 .. i + 3).and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+#[test]
+fn test_font_face() {
+    let css = "/* fonts */ @font-face { font-family: 'My Font'; font-weight: bold;
+        src: local(Foo), url(\"data:font/ttf;base64,AAEA AAA=\") format('truetype'), url(font.woff) }
+        text { fill: red }";
+    let faces: Vec<FontFace> = font_face_rules(&strip_comments(css)).filter_map(FontFace::parse_block).collect();
+    assert_eq!(faces.len(), 1);
+    assert_eq!(faces[0].family, "My Font");
+    assert_eq!(faces[0].weight, Some(FontWeight::Absolute(700)));
+    match faces[0].sources[..] {
+        [FontSource::Data { ref mime, ref data }, FontSource::Url(ref url)] => {
+            assert_eq!(mime, "font/ttf");
+            assert_eq!(&data[..], &[0, 1, 0, 0, 0][..]);
+            assert_eq!(url, "font.woff");
+        }
+        _ => panic!("{:?}", faces[0].sources)
+    }
+}
+
+#[test]
+fn test_data_uri() {
+    assert_eq!(data_uri("data:font/woff;base64,aGk="), Some(("font/woff".into(), b"hi".to_vec())));
+    assert_eq!(data_uri("data:Text/Plain;charset=utf-8,a%20b%2"), Some(("text/plain".into(), b"a b%2".to_vec())));
+    assert_eq!(data_uri("data:,"), Some(("".into(), vec![])));
+    assert_eq!(data_uri("data:font/ttf;base64"), None);
+    assert_eq!(data_uri("font.ttf"), None);
+}
+
+#[test]
+fn test_base64() {
+    assert_eq!(base64("aGVsbG8="), Some(b"hello".to_vec()));
+    assert_eq!(base64("aGVs\n bG8"), Some(b"hello".to_vec()));
+    // the standard and the url safe alphabet
+    assert_eq!(base64("+/8="), Some(vec![0xfb, 0xff]));
+    assert_eq!(base64("-_8"), Some(vec![0xfb, 0xff]));
+    assert_eq!(base64(""), Some(vec![]));
+    assert_eq!(base64("a*b"), None);
+}
@@ -10,8 +10,11 @@ pub struct TagSvg {
     pub id: Option<String>,
     pub items: Vec<Arc<Item>>,
     pub view_box: Option<Rect>,
+    pub x: Option<LengthX>,
+    pub y: Option<LengthY>,
     pub width: Option<LengthX>,
     pub height: Option<LengthY>,
+    pub preserve_aspect_ratio: PreserveAspectRatio,
     pub attrs: Attrs,
 }
 
@@ -19,6 +22,10 @@ pub struct TagSvg {
 pub struct Svg {
     pub named_items: ItemCollection,
     pub root: Arc<Item>,
+    /// the `@font-face` rules of all `<style>` elements
+    pub font_faces: Vec<Arc<FontFace>>,
+    /// the `<font>` elements, each an `Item::Font`
+    pub fonts: Vec<Arc<Item>>,
 }
 impl Tag for TagSvg {
     fn id(&self) -> Option<&str> {
@@ -32,14 +39,17 @@ impl Tag for TagSvg {
 impl ParseNode for TagSvg {
     fn parse_node(node: &Node) -> Result<TagSvg, Error> {
         let view_box = node.attribute("viewBox").map(Rect::parse).transpose()?;
+        let x = node.attribute("x").map(LengthX::parse).transpose()?;
+        let y = node.attribute("y").map(LengthY::parse).transpose()?;
         let width = node.attribute("width").map(LengthX::parse).transpose()?;
         let height = node.attribute("height").map(LengthY::parse).transpose()?;
+        let preserve_aspect_ratio = parse_attr_or(node, "preserveAspectRatio", PreserveAspectRatio::default())?;
         let id = node.attribute("id").map(|s| s.into());
         let attrs = Attrs::parse(node)?;
 
         let items = parse_node_list(node.children())?;
     
-        Ok(TagSvg { items, view_box, id, attrs, width, height })
+        Ok(TagSvg { items, view_box, id, attrs, x, y, width, height, preserve_aspect_ratio })
     }
 }
 
@@ -49,15 +59,21 @@ impl Svg {
     }
     pub fn from_str(text: &str) -> Result<Svg, Error> {
         let doc = Document::parse(text)?;
-        let root = parse_node(&doc.root_element(), true, true);
+        let root = parse_node(&doc.root_element(), true, true, WhiteSpace::Default);
         let root_item = Arc::new(root?.ok_or(Error::NotSvg)?);
 
         let mut named_items = ItemCollection::new();
         link(&mut named_items, &root_item);
 
+        let mut font_faces = vec![];
+        let mut fonts = vec![];
+        collect_fonts(&mut font_faces, &mut fonts, &root_item);
+
         Ok(Svg {
             root: root_item,
             named_items,
+            font_faces,
+            fonts,
         })
     }
     pub fn from_data(data: &[u8]) -> Result<Svg, Error> {
@@ -73,4 +89,15 @@ impl Svg {
             Self::from_str(text)
         }
     }
-}
\ No newline at end of file
+}
+
+fn collect_fonts(font_faces: &mut Vec<Arc<FontFace>>, fonts: &mut Vec<Arc<Item>>, item: &Arc<Item>) {
+    match **item {
+        Item::Style(ref style) => font_faces.extend(style.font_faces.iter().cloned()),
+        Item::Font(_) => fonts.push(item.clone()),
+        _ => {}
+    }
+    for child in item.children() {
+        collect_fonts(font_faces, fonts, child);
+    }
+}
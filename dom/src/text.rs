@@ -8,6 +8,10 @@ pub struct TagText {
     pub items: Vec<Arc<Item>>,
     pub pos: GlyphPos,
     pub attrs: Attrs,
+    pub text_length: Option<LengthX>,
+    pub length_adjust: LengthAdjust,
+    /// lines are wrapped to this length, `None` for `auto`
+    pub inline_size: Option<LengthX>,
 }
 impl Tag for TagText {
     fn id(&self) -> Option<&str> {
@@ -27,6 +31,9 @@ impl ParseNode for TagText {
             var dy,
             var rotate,
             var id,
+            var text_length ("textLength"): Option<LengthX>,
+            var length_adjust ("lengthAdjust"): LengthAdjust = LengthAdjust::Spacing,
+            var inline_size ("inline-size"): Option<LengthX> => auto_or(LengthX::parse),
             _ => items,
         });
 
@@ -35,11 +42,74 @@ impl ParseNode for TagText {
             attrs: Attrs::parse(node)?,
             id,
             items,
+            text_length,
+            length_adjust,
+            inline_size,
         })
     }
 }
 
+/// the SVG Tiny 1.2 `<textArea>`, text wrapped to a rectangle
 #[derive(Clone, Debug)]
+pub struct TagTextArea {
+    pub id: Option<String>,
+    pub items: Vec<Arc<Item>>,
+    pub attrs: Attrs,
+    pub x: LengthX,
+    pub y: LengthY,
+    /// `None` for `auto`
+    pub width: Option<LengthX>,
+    pub height: Option<LengthY>,
+}
+impl Tag for TagTextArea {
+    fn id(&self) -> Option<&str> {
+        self.id.as_ref().map(|s| s.as_str())
+    }
+    fn children(&self) -> &[Arc<Item>] {
+        &self.items
+    }
+}
+impl ParseNode for TagTextArea {
+    fn parse_node(node: &Node) -> Result<TagTextArea, Error> {
+        parse!(node => {
+            var x: LengthX,
+            var y: LengthY,
+            var width: Option<LengthX> => auto_or(LengthX::parse),
+            var height: Option<LengthY> => auto_or(LengthY::parse),
+            var id,
+            _ => items,
+        });
+
+        Ok(TagTextArea {
+            attrs: Attrs::parse(node)?,
+            id,
+            items,
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+}
+
+/// a forced line break in a `<textArea>`
+#[derive(Clone, Debug)]
+pub struct TagTBreak;
+impl Tag for TagTBreak {}
+impl ParseNode for TagTBreak {
+    fn parse_node(_node: &Node) -> Result<TagTBreak, Error> {
+        Ok(TagTBreak)
+    }
+}
+
+fn auto_or<T>(f: impl Fn(&str) -> Result<T, Error>) -> impl Fn(&str) -> Result<Option<T>, Error> {
+    move |s| match s {
+        "auto" => Ok(None),
+        _ => Ok(Some(f(s)?))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct GlyphPos {
     pub x: Option<OneOrMany<LengthX>>,
     pub y: Option<OneOrMany<LengthY>>,
@@ -50,12 +120,32 @@ pub struct GlyphPos {
     pub rotate: Option<OneOrMany<f32>>,
 }
 
+/// how `textLength` is achieved
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LengthAdjust {
+    /// only the space between the characters changes
+    Spacing,
+    /// the glyphs are stretched as well
+    SpacingAndGlyphs,
+}
+impl Parse for LengthAdjust {
+    fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "spacing" => Ok(LengthAdjust::Spacing),
+            "spacingAndGlyphs" => Ok(LengthAdjust::SpacingAndGlyphs),
+            _ => Err(Error::InvalidAttributeValue(s.into()))
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct TagTSpan {
     pub id: Option<String>,
     pub items: Vec<Arc<Item>>,
     pub attrs: Attrs,
     pub pos: GlyphPos,
+    pub text_length: Option<LengthX>,
+    pub length_adjust: LengthAdjust,
 }
 impl Tag for TagTSpan {
     fn id(&self) -> Option<&str> {
@@ -74,6 +164,8 @@ impl ParseNode for TagTSpan {
             var dy,
             var rotate,
             var id,
+            var text_length ("textLength"): Option<LengthX>,
+            var length_adjust ("lengthAdjust"): LengthAdjust = LengthAdjust::Spacing,
             _ => items,
         });
         let attrs = Attrs::parse(node)?;
@@ -83,6 +175,8 @@ impl ParseNode for TagTSpan {
             id,
             items,
             pos: GlyphPos { x, y, dx, dy, rotate },
+            text_length,
+            length_adjust,
         })
     }
 }
@@ -90,12 +184,14 @@ impl ParseNode for TagTSpan {
 
 #[derive(Clone, Debug)]
 pub struct TagTRef {
+    pub id: Option<String>,
     pub href: Option<String>,
+    pub attrs: Attrs,
 }
 
 impl Tag for TagTRef {
     fn id(&self) -> Option<&str> {
-        None
+        self.id.as_ref().map(|s| s.as_str())
     }
     fn children(&self) -> &[Arc<Item>] {
         &[]
@@ -104,10 +200,27 @@ impl Tag for TagTRef {
 impl ParseNode for TagTRef {
     fn parse_node(node: &Node) -> Result<TagTRef, Error> {
         let href = href(node);
-        Ok(TagTRef { href })
+        let id = node.attribute("id").map(|s| s.into());
+        let attrs = Attrs::parse(node)?;
+        Ok(TagTRef { id, href, attrs })
     }
 }
 
+/// all character data of `item` and its descendants, like the one a `<tref>` refers to
+pub fn text_content(item: &Item) -> String {
+    fn collect(item: &Item, out: &mut String) {
+        match *item {
+            Item::String(ref s) => out.push_str(s),
+            _ => for child in item.children() {
+                collect(child, out);
+            }
+        }
+    }
+    let mut out = String::new();
+    collect(item, &mut out);
+    out
+}
+
 fn one_or_many<'a, T: 'a>(f: impl Fn(Length) -> T + 'a) -> impl Fn(&str) -> Result<OneOrMany<T>, Error> + 'a {
     use svgtypes::LengthListParser;
     move |s| {
@@ -136,4 +249,49 @@ impl Parse for OneOrMany<LengthY> {
     fn parse(s: &str) -> Result<OneOrMany<LengthY>, Error> {
         one_or_many(LengthY)(s)
     }
-}
\ No newline at end of file
+}
+
+/// handling of white space in text content, from the `white-space` property or `xml:space`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WhiteSpace {
+    /// spaces are collapsed and newlines removed (`xml:space="default"`)
+    Default,
+    /// spaces are collapsed, newlines become spaces
+    Normal,
+    /// all spaces are kept (`xml:space="preserve"`)
+    Pre,
+    PreWrap,
+    /// spaces are collapsed, but newlines are kept
+    PreLine,
+}
+impl Parse for WhiteSpace {
+    fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "normal" | "nowrap" => Ok(WhiteSpace::Normal),
+            "pre" => Ok(WhiteSpace::Pre),
+            "pre-wrap" | "break-spaces" => Ok(WhiteSpace::PreWrap),
+            "pre-line" => Ok(WhiteSpace::PreLine),
+            _ => Err(Error::InvalidAttributeValue(s.into()))
+        }
+    }
+}
+
+/// the white space handling of the content of `node`. both `white-space` and `xml:space` are inherited,
+/// the property takes precedence over the attribute of the same element.
+pub(crate) fn white_space(node: &Node) -> WhiteSpace {
+    for node in node.ancestors().filter(|n| n.is_element()) {
+        let style = node.attribute("style").into_iter().flat_map(style_list)
+            .filter(|&(key, _)| key == "white-space")
+            .map(|(_, val)| val)
+            .last();
+        if let Some(Ok(white_space)) = style.or(node.attribute("white-space")).map(WhiteSpace::parse) {
+            return white_space;
+        }
+        match node.attribute((roxmltree::NS_XML_URI, "space")) {
+            Some("preserve") => return WhiteSpace::Pre,
+            Some("default") => return WhiteSpace::Default,
+            _ => {}
+        }
+    }
+    WhiteSpace::Default
+}
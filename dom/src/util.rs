@@ -90,6 +90,65 @@ impl Rect {
     }
 }
 
+/// position of the viewBox in the viewport along one axis
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Align {
+    Min,
+    Mid,
+    Max,
+}
+impl Align {
+    /// fraction of the free space before the viewBox
+    pub fn fraction(self) -> f32 {
+        match self {
+            Align::Min => 0.0,
+            Align::Mid => 0.5,
+            Align::Max => 1.0,
+        }
+    }
+}
+
+/// how a viewBox is fitted into its viewport
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PreserveAspectRatio {
+    /// alignment along x and y, `None` to scale non-uniformly
+    pub align: Option<(Align, Align)>,
+    /// cover the viewport instead of fitting into it
+    pub slice: bool,
+}
+impl Default for PreserveAspectRatio {
+    fn default() -> Self {
+        PreserveAspectRatio { align: Some((Align::Mid, Align::Mid)), slice: false }
+    }
+}
+impl Parse for PreserveAspectRatio {
+    fn parse(s: &str) -> Result<Self, Error> {
+        let align = |a: &str| match a {
+            "Min" => Ok(Align::Min),
+            "Mid" => Ok(Align::Mid),
+            "Max" => Ok(Align::Max),
+            _ => Err(Error::InvalidAttributeValue(s.into()))
+        };
+        let mut parts = s.split_whitespace().peekable();
+        // `defer` only applies to images
+        if parts.peek() == Some(&"defer") {
+            parts.next();
+        }
+        let align = match parts.next() {
+            Some("none") => None,
+            // ascii only, so that the slices are at char boundaries
+            Some(a) if a.len() == 8 && a.is_ascii() && a.starts_with('x') && a.as_bytes()[4] == b'Y' => Some((align(&a[1..4])?, align(&a[5..8])?)),
+            _ => return Err(Error::InvalidAttributeValue(s.into()))
+        };
+        let slice = match parts.next() {
+            None | Some("meet") => false,
+            Some("slice") => true,
+            _ => return Err(Error::InvalidAttributeValue(s.into()))
+        };
+        Ok(PreserveAspectRatio { align, slice })
+    }
+}
+
 pub fn inherit<T>(f: impl Fn(&str) -> Result<T, Error>) -> impl Fn(&str) -> Result<Option<T>, Error> {
     move |s | match s {
         "inherit" => Ok(None),
@@ -211,6 +270,22 @@ pub fn parse_attr_or<'a, 'i, T: Parse>(node: &Node<'a, 'i>, attr: &str, default:
     }
 }
 
+/// coordinate system for the content of clip paths, masks and similar
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Units {
+    UserSpaceOnUse,
+    ObjectBoundingBox,
+}
+impl Parse for Units {
+    fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "userSpaceOnUse" => Ok(Units::UserSpaceOnUse),
+            "objectBoundingBox" => Ok(Units::ObjectBoundingBox),
+            _ => Err(Error::InvalidAttributeValue(s.into()))
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum Axis {
     X,
@@ -234,4 +309,13 @@ impl Parse for OneOrMany<f32> {
     fn parse(s: &str) -> Result<Self, Error> {
         crate::parser::one_or_many_f32(s)
     }
-}
\ No newline at end of file
+}
+#[test]
+fn test_preserve_aspect_ratio() {
+    assert_eq!(PreserveAspectRatio::parse("xMinYMax slice").unwrap(), PreserveAspectRatio { align: Some((Align::Min, Align::Max)), slice: true });
+    assert_eq!(PreserveAspectRatio::parse("defer xMidYMid").unwrap(), PreserveAspectRatio::default());
+    assert_eq!(PreserveAspectRatio::parse("none").unwrap().align, None);
+    assert!(PreserveAspectRatio::parse("xMidYCenter").is_err());
+    assert!(PreserveAspectRatio::parse("xMiéYMax").is_err());
+    assert!(PreserveAspectRatio::parse("xMiéMax").is_err());
+}
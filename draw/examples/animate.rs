@@ -9,7 +9,7 @@ use std::sync::Arc;
 
 struct AnimatedSvg {
     svg: Svg,
-    fonts: FontCollection,
+    fonts: Arc<FontCollection>,
     start: Instant
 }
 
@@ -23,7 +23,7 @@ impl Interactive for AnimatedSvg {
         ctx.request_redraw();
     }
     fn scene(&mut self, ctx: &mut Context) -> Scene {
-        let ctx = DrawContext::new(&self.svg, &self.fonts);
+        let ctx = DrawContext::new(&self.svg, self.fonts.clone());
         let mut options = DrawOptions::new(&ctx);
         options.time = Time::from_seconds(self.start.elapsed().as_secs_f64());
         ctx.compose_with_options(&options)
@@ -38,11 +38,11 @@ fn main() {
     config.zoom = true;
     config.pan = false;
 
-    let fonts = FontCollection::from_fonts(vec![
+    let fonts = Arc::new(FontCollection::from_fonts(vec![
         Font::load(include_bytes!("../../resources/latinmodern-math.otf")),
         Font::load(include_bytes!("../../resources/NotoNaskhArabic-Regular.ttf")),
         Font::load(include_bytes!("../../resources/NotoSerifBengali-Regular.ttf")),
-    ]);
+    ]));
 
     let svg = Svg::from_data(&data).unwrap();
     show(AnimatedSvg {
@@ -13,14 +13,14 @@ fn main() {
     let data = std::fs::read(input).unwrap();
     let output = args.next().unwrap();
 
-    let fonts = FontCollection::from_fonts(vec![
+    let fonts = Arc::new(FontCollection::from_fonts(vec![
         Font::load(include_bytes!("../../resources/latinmodern-math.otf")),
         Font::load(include_bytes!("../../resources/NotoNaskhArabic-Regular.ttf")),
         Font::load(include_bytes!("../../resources/NotoSerifBengali-Regular.ttf")),
-    ]);
+    ]));
 
     let svg = Svg::from_data(&data).unwrap();
-    let scene = DrawContext::new(&svg, &fonts).compose();
+    let scene = DrawContext::new(&svg, fonts.clone()).compose();
     let image = Rasterizer::new_with_level(RendererLevel::D3D9).rasterize(scene, Some(ColorF::white()));
     image.save(&output).unwrap();
 }
@@ -22,11 +22,11 @@ fn main() {
     config.zoom = true;
     config.pan = true;
 
-    let fonts = FontCollection::from_fonts(vec![
+    let fonts = Arc::new(FontCollection::from_fonts(vec![
         Font::load(include_bytes!("../../resources/latinmodern-math.otf")),
         Font::load(include_bytes!("../../resources/NotoNaskhArabic-Regular.ttf")),
         Font::load(include_bytes!("../../resources/NotoSerifBengali-Regular.ttf")),
-    ]);
+    ]));
 
     let svg = Svg::from_data(&data).unwrap();
     show(View::new(svg, fonts), config)
@@ -34,12 +34,12 @@ fn main() {
 
 struct View {
     svg: Svg,
-    fonts: FontCollection,
+    fonts: Arc<FontCollection>,
     view_box: Option<RectF>
 }
 impl View {
-    fn new(svg: Svg, fonts: FontCollection) -> View {
-        let view_box = DrawContext::new(&svg, &fonts).view_box();
+    fn new(svg: Svg, fonts: Arc<FontCollection>) -> View {
+        let view_box = DrawContext::new(&svg, fonts.clone()).view_box();
         View {
             svg, fonts, view_box
         }
@@ -53,7 +53,7 @@ impl Interactive for View {
         } else {
             scene.set_view_box(RectF::new(Vector2F::zero(), ctx.window_size()))
         };
-        DrawContext::new(&self.svg, &self.fonts)
+        DrawContext::new(&self.svg, self.fonts.clone())
             .compose_to_with_transform(&mut scene, dbg!(ctx.view_transform()));
         scene
     }
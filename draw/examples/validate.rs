@@ -9,11 +9,11 @@ use image::{io::Reader, Rgba};
 
 fn main() {
     env_logger::init();
-    let fonts = FontCollection::from_fonts(vec![
+    let fonts = Arc::new(FontCollection::from_fonts(vec![
         Font::load(include_bytes!("../../resources/latinmodern-math.otf")),
         Font::load(include_bytes!("../../resources/NotoNaskhArabic-Regular.ttf")),
         Font::load(include_bytes!("../../resources/NotoSerifBengali-Regular.ttf")),
-    ]);
+    ]));
 
     let test_data = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../test_data"));
     let svgs = test_data.join("svg").read_dir().unwrap();
@@ -28,7 +28,7 @@ fn main() {
         let data = std::fs::read(e.path()).unwrap();
 
         let svg = Svg::from_data(&data).unwrap();
-        let scene = DrawContext::new(&svg, &fonts).compose();
+        let scene = DrawContext::new(&svg, fonts.clone()).compose();
         let mut image = Rasterizer::new().rasterize(scene, Some(ColorF::white()));
 
         let mut png_path = pngs.join(&name);
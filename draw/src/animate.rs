@@ -22,7 +22,7 @@ impl<T> Resolve for Animate<T> where T: Resolve, T::Output: Interpolate {
 
         match self.mode {
             AnimationMode::Absolute { ref from, ref to } => {
-                Some(from.resolve(options).lerp(to.resolve(options), x))
+                Some(from.resolve(options).lerp_with(to.resolve(options), x, options))
             }
             AnimationMode::Relative { ref by } => {
                 Some(by.resolve(options).scale(x))
@@ -44,7 +44,7 @@ impl<T> Resolve for Animate<T> where T: Resolve, T::Output: Interpolate {
                             CalcMode::Spline => splines.get(idx - 1).unwrap().y_for_x(fragment_time),
                             _ => fragment_time // whatever
                         };
-                        Some(v0.resolve(options).lerp(v1.resolve(options), mapped_time))
+                        Some(v0.resolve(options).lerp_with(v1.resolve(options), mapped_time, options))
                     }
                     _ => None
                 }
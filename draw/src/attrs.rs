@@ -4,12 +4,19 @@ use crate::animate::*;
 use pathfinder_content::{
     fill::{FillRule}
 };
-use svgtypes::{Length, Color};
+use svgtypes::{Length};
 
 wrap_option_iterpolate!(Fill);
 
 wrap_option_iterpolate!(Stroke);
 
+wrap_option_iterpolate!(CurrentColor);
+
+discrete_interpolate!(bool);
+discrete_interpolate!(Visibility);
+
+wrap_option_iterpolate!(VisibilityAttr);
+
 impl Resolve for Fill {
     type Output = Paint;
     fn resolve(&self, options: &Options) -> Self::Output {
@@ -21,4 +28,38 @@ impl Resolve for Stroke {
     fn resolve(&self, options: &Options) -> Self::Output {
         self.0.clone().unwrap_or_else(|| options.stroke.clone())
     }
-}
\ No newline at end of file
+}
+impl Resolve for CurrentColor {
+    type Output = Color;
+    fn resolve(&self, options: &Options) -> Self::Output {
+        self.0.clone().unwrap_or_else(|| options.color.clone())
+    }
+}
+
+impl Resolve for Display {
+    type Output = bool;
+    fn resolve(&self, _options: &Options) -> bool {
+        self.0
+    }
+}
+/// offset of the baseline in user units, positive is up
+impl Resolve for BaselineShift {
+    type Output = f32;
+    fn resolve(&self, options: &Options) -> f32 {
+        match *self {
+            BaselineShift::Baseline => 0.0,
+            BaselineShift::Sub => options.script_offsets().0,
+            BaselineShift::Super => options.script_offsets().1,
+            BaselineShift::Length(length) => match length.unit {
+                LengthUnit::Percent => 0.01 * length.num as f32 * options.font_size,
+                _ => options.resolve_length(length).unwrap_or_default()
+            }
+        }
+    }
+}
+impl Resolve for VisibilityAttr {
+    type Output = Visibility;
+    fn resolve(&self, options: &Options) -> Self::Output {
+        self.0.unwrap_or(options.visibility)
+    }
+}
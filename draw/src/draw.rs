@@ -4,6 +4,7 @@ use pathfinder_content::{
     stroke::{OutlineStrokeToFill, StrokeStyle, LineCap, LineJoin},
     fill::{FillRule},
     dash::OutlineDash,
+    effects::BlendMode,
 };
 use pathfinder_renderer::{
     scene::{Scene, DrawPath, ClipPath, ClipPathId},
@@ -13,11 +14,12 @@ use pathfinder_color::ColorU;
 use svgtypes::{Length};
 use std::sync::Arc;
 use crate::gradient::BuildGradient;
+use crate::path::clip_outline;
 #[cfg(feature="text")]
 use crate::text::{FontCache};
 use isolang::Language;
 #[cfg(feature="text")]
-use svg_text::FontCollection;
+use svg_text::{FontCollection, FontDatabase};
 use std::rc::Rc;
 use std::borrow::Cow;
 use std::ops::{Deref, DerefMut};
@@ -43,12 +45,27 @@ impl<'a> DrawContext<'a> {
     }
 
     #[cfg(feature="text")]
-    pub fn new(svg: &'a Svg, fallback_fonts: &'a FontCollection) -> Self {
+    pub fn new(svg: &'a Svg, fallback_fonts: Arc<FontCollection>) -> Self {
+        let mut font_cache = FontCache::new(fallback_fonts);
+        if svg.font_faces.len() > 0 || svg.fonts.len() > 0 {
+            font_cache.set_document_fonts(svg);
+        }
         DrawContext {
             svg,
             dpi: 75.0,
 
-            font_cache: Some(FontCache::new(fallback_fonts)),
+            font_cache: Some(font_cache),
+        }
+    }
+    /// set the resolution used to convert absolute units (in, cm, mm, pt, pc) to user units
+    pub fn set_dpi(&mut self, dpi: f32) {
+        self.dpi = dpi;
+    }
+    /// select fonts by their properties from `database`, before falling back to the global fonts
+    #[cfg(feature="text")]
+    pub fn set_font_database(&mut self, database: &'a FontDatabase) {
+        if let Some(ref mut font_cache) = self.font_cache {
+            font_cache.set_database(database);
         }
     }
     pub fn resolve(&self, id: &str) -> Option<&Arc<Item>> {
@@ -100,11 +117,18 @@ impl<'a> DrawContext<'a> {
     pub fn view_box(&'a self) -> Option<RectF> {
         let options = BoundsOptions::new(self);
         
-        if let Item::Svg(TagSvg { view_box: Some(r), width, height, .. }) = &*self.svg.root {
-            if let Some(size) = Vector(
-                width.unwrap_or(r.width),
-                height.unwrap_or(r.height)
-            ).try_resolve(&options) {
+        if let Item::Svg(TagSvg { view_box: Some(r), width, height, preserve_aspect_ratio, .. }) = &*self.svg.root {
+            let width = width.and_then(|l| l.try_resolve(&options));
+            let height = height.and_then(|l| l.try_resolve(&options));
+            if let Some(vb_size) = r.size().try_resolve(&options) {
+                // with only one dimension given, the other one follows from the aspect ratio of the view box
+                let keep_ratio = preserve_aspect_ratio.align.is_some();
+                let size = match (width, height) {
+                    (Some(w), Some(h)) => vec2f(w, h),
+                    (Some(w), None) if keep_ratio => vec2f(w, w * vb_size.y() / vb_size.x()),
+                    (None, Some(h)) if keep_ratio => vec2f(h * vb_size.x() / vb_size.y(), h),
+                    (w, h) => vec2f(w.unwrap_or(vb_size.x()), h.unwrap_or(vb_size.y())),
+                };
                 return Some(RectF::new(Vector2F::zero(), size));
             }
         }
@@ -116,6 +140,8 @@ impl<'a> DrawContext<'a> {
 pub struct Options<'a> {
     pub ctx: &'a DrawContext<'a>,
 
+    pub color: Color,
+
     pub fill: Paint,
     pub fill_rule: FillRule,
     pub fill_opacity: f32,
@@ -137,15 +163,42 @@ pub struct Options<'a> {
     pub time: Time,
 
     pub font_size: f32,
+    pub font_family: Rc<[FontFamily]>,
+    pub font_weight: u16,
+    pub font_style: FontStyle,
+    pub font_stretch: f32,
+    pub font_kerning: bool,
+    /// extra space after each character and word separator, in user units
+    pub letter_spacing: f32,
+    pub word_spacing: f32,
     pub direction: TextFlow,
+    pub writing_mode: WritingMode,
+    pub text_orientation: TextOrientation,
+    pub text_anchor: TextAnchor,
+    pub text_align: TextAlign,
+    pub dominant_baseline: Baseline,
+    pub alignment_baseline: Baseline,
+    /// sum of the baseline shifts of the ancestors, in user units (positive is up)
+    pub baseline_shift: f32,
 
     pub lang: Option<Language>,
+
+    pub paint_order: PaintOrder,
+
+    /// `mix-blend-mode` of the element, used when its layer is composited
+    pub blend_mode: BlendMode,
+
+    pub visibility: Visibility,
+
+    pub color_interpolation: ColorInterpolation,
+    pub color_interpolation_filters: ColorInterpolation,
 }
 impl<'a> Options<'a> {
     pub fn new(ctx: &'a DrawContext<'a>) -> Options<'a> {
         Options {
             ctx,
             opacity: 1.0,
+            color: Color::black(),
             fill: Paint::black(),
             fill_rule: FillRule::EvenOdd,
             fill_opacity: 1.0,
@@ -159,20 +212,47 @@ impl<'a> Options<'a> {
             stroke_dasharray: None,
             stroke_dashoffset: 0.0,
             transform: Transform2F::from_scale(10.),
-            clip_rule: FillRule::EvenOdd,
+            clip_rule: FillRule::Winding,
             view_box: None,
             time: Time::start(),
             font_size: 20.,
+            font_family: Rc::new([]),
+            font_weight: 400,
+            font_style: FontStyle::Normal,
+            font_stretch: 100.,
+            font_kerning: true,
+            letter_spacing: 0.,
+            word_spacing: 0.,
             direction: TextFlow::LeftToRight,
+            writing_mode: WritingMode::HorizontalTb,
+            text_orientation: TextOrientation::Mixed,
+            text_anchor: TextAnchor::Start,
+            text_align: TextAlign::Start,
+            dominant_baseline: Baseline::Auto,
+            alignment_baseline: Baseline::Auto,
+            baseline_shift: 0.0,
             lang: None,
+            paint_order: PaintOrder::default(),
+            blend_mode: BlendMode::SrcOver,
+            visibility: Visibility::Visible,
+            color_interpolation: ColorInterpolation::SRGB,
+            // the spec default is linearRGB, but pattern filters run on sRGB values and
+            // linearRGB is only approximated (see `filter::linear_matrix`), so stay in sRGB
+            // unless a document asks for it.
+            color_interpolation_filters: ColorInterpolation::SRGB,
         }
     }
+    pub fn is_visible(&self) -> bool {
+        self.visibility == Visibility::Visible
+    }
     pub fn has_stroke(&self) -> bool {
+        self.is_visible() &&
         self.opacity > 0.0 &&
         self.stroke_opacity > 0. &&
         !matches!(self.stroke, Paint::None)
     }
     pub fn has_fill(&self) -> bool {
+        self.is_visible() &&
         self.opacity > 0.0 &&
         self.fill_opacity > 0. &&
         !matches!(self.fill, Paint::None)
@@ -188,89 +268,210 @@ impl<'a> Options<'a> {
     }
     pub fn apply(&self, attrs: &Attrs) -> Options<'a> {
         let mut stroke_style = self.stroke_style;
-        if let Some(length) = attrs.stroke_width.resolve(self) {
-            stroke_style.line_width = length;
-        }
-        Options {
+        stroke_style.line_width = self.resolve_inherited("stroke-width", &attrs.stroke_width, self.stroke_style.line_width);
+        // animated colors are interpolated in the color space of the element itself
+        let color_interpolation = attrs.color_interpolation.unwrap_or(self.color_interpolation);
+        let colors = if color_interpolation == self.color_interpolation {
+            Cow::Borrowed(self)
+        } else {
+            Cow::Owned(Options { color_interpolation, .. self.clone() })
+        };
+        let mut options = Options {
             clip_rule: attrs.clip_rule.unwrap_or(self.clip_rule),
-            opacity: attrs.opacity.resolve(self).unwrap_or(1.0),
+            opacity: self.opacity * attrs.opacity.resolve(self).unwrap_or(1.0),
             transform: self.transform * attrs.transform.resolve(self),
-            fill: attrs.fill.resolve(self),
+            color: attrs.color.resolve(&colors),
+            fill: attrs.fill.resolve(&colors),
             fill_rule: attrs.fill_rule.unwrap_or(self.fill_rule),
             fill_opacity: attrs.fill_opacity.resolve(self).unwrap_or(self.fill_opacity),
-            stroke: attrs.stroke.resolve(self),
+            stroke: attrs.stroke.resolve(&colors),
             stroke_style,
             stroke_opacity: attrs.stroke_opacity.resolve(self).unwrap_or(self.stroke_opacity),
             stroke_dasharray: attrs.stroke_dasharray.resolve(self),
             direction: attrs.direction.unwrap_or(self.direction),
-            font_size: attrs.font_size.resolve(self).unwrap_or(self.font_size),
+            writing_mode: attrs.writing_mode.unwrap_or(self.writing_mode),
+            text_orientation: attrs.text_orientation.unwrap_or(self.text_orientation),
+            text_anchor: attrs.text_anchor.unwrap_or(self.text_anchor),
+            text_align: attrs.text_align.unwrap_or(self.text_align),
+            font_size: self.resolve_inherited("font-size", &attrs.font_size, self.font_size),
+            font_family: match attrs.font_family {
+                Some(ref list) => list.0.as_slice().into(),
+                None => self.font_family.clone()
+            },
+            font_weight: attrs.font_weight.map(|w| w.resolve(self.font_weight)).unwrap_or(self.font_weight),
+            font_style: attrs.font_style.unwrap_or(self.font_style),
+            font_stretch: attrs.font_stretch.map(|s| s.0).unwrap_or(self.font_stretch),
+            font_kerning: attrs.font_kerning.unwrap_or(self.font_kerning),
             lang: attrs.lang.or(self.lang),
+            paint_order: attrs.paint_order.unwrap_or(self.paint_order),
+            blend_mode: attrs.mix_blend_mode,
+            visibility: attrs.visibility.resolve(self),
+            color_interpolation,
+            color_interpolation_filters: attrs.color_interpolation_filters.unwrap_or(self.color_interpolation_filters),
+            dominant_baseline: attrs.dominant_baseline.unwrap_or(self.dominant_baseline),
+            alignment_baseline: attrs.alignment_baseline,
             .. *self
+        };
+        // these depend on the font size of the element itself
+        options.baseline_shift = self.baseline_shift + attrs.baseline_shift.resolve(&options);
+        options.letter_spacing = options.resolve_inherited("letter-spacing", &attrs.letter_spacing, self.letter_spacing);
+        options.word_spacing = options.resolve_inherited("word-spacing", &attrs.word_spacing, self.word_spacing);
+        if let Some(Kerning::Length(spacing)) = attrs.kerning {
+            options.font_kerning = false;
+            options.letter_spacing += spacing.resolve(&options);
+        }
+        options
+    }
+    /// the value of an inherited property, or that of the parent if it is not set or can't be resolved
+    fn resolve_inherited<T>(&self, name: &str, value: &T, parent: f32) -> f32
+        where T: Resolve<Output=Option<f32>> + std::fmt::Debug
+    {
+        match value.try_resolve(self) {
+            Some(Some(value)) => value,
+            Some(None) => parent,
+            None => {
+                warn!("can't resolve {} {:?}, using the parent value {}", name, value, parent);
+                parent
+            }
         }
     }
     fn resolve_paint(&self, paint: &Paint, opacity: f32) -> Option<PaPaint> {
-        let opacity = opacity * self.opacity;
+        let total_opacity = opacity * self.opacity;
         match *paint {
-            Paint::Color(ref c) => Some(PaPaint::from_color(c.color_u(opacity))),
-            Paint::Ref(ref id) => match self.ctx.svg.named_items.get(id).map(|arc| &**arc) {
-                Some(Item::LinearGradient(ref gradient)) => Some(PaPaint::from_gradient(gradient.build(self, opacity))),
-                Some(Item::RadialGradient(ref gradient)) => Some(PaPaint::from_gradient(gradient.build(self, opacity))),
-                r => {
-                    dbg!(id, r);
-                    None
+            Paint::Color(ref c) => Some(PaPaint::from_color(c.color_u(total_opacity))),
+            Paint::CurrentColor => Some(PaPaint::from_color(self.color.color_u(total_opacity))),
+            Paint::Ref(ref id, ref fallback) => match self.ctx.svg.named_items.get(id).map(|arc| &**arc) {
+                Some(Item::LinearGradient(ref gradient)) => Some(PaPaint::from_gradient(gradient.build(self, total_opacity))),
+                Some(Item::RadialGradient(ref gradient)) => Some(PaPaint::from_gradient(gradient.build(self, total_opacity))),
+                r => match fallback {
+                    Some(ref fallback) => self.resolve_paint(fallback, opacity),
+                    None => {
+                        debug!("paint server {:?} not found: {:?}", id, r);
+                        None
+                    }
                 }
             }
             _ => None
         }
     }
+    /// size of an ex: the x-height of the font, or half an em if it is not known
+    fn x_height(&self) -> f32 {
+        #[cfg(feature="text")]
+        let x_height = self.ctx.font_cache.as_ref().and_then(|cache| cache.x_height(self));
+        #[cfg(not(feature="text"))]
+        let x_height = None;
+
+        x_height.unwrap_or(0.5) * self.font_size
+    }
+    /// offsets of the (subscript, superscript) baselines in user units, positive is up
+    pub fn script_offsets(&self) -> (f32, f32) {
+        #[cfg(feature="text")]
+        let offsets = self.ctx.font_cache.as_ref().map(|cache| cache.script_offsets(self));
+        #[cfg(not(feature="text"))]
+        let offsets = None;
+
+        let (sub, sup) = offsets.unwrap_or((-0.2, 0.4));
+        (sub * self.font_size, sup * self.font_size)
+    }
+    /// size of one unit in user units, `None` for percentages
+    fn unit_scale(&self, unit: LengthUnit) -> Option<f32> {
+        let dpi = self.ctx.dpi;
+        Some(match unit {
+            LengthUnit::None | LengthUnit::Px => 1.0,
+            LengthUnit::In => dpi,
+            LengthUnit::Cm => dpi * (1.0 / 2.54),
+            LengthUnit::Mm => dpi * (1.0 / 25.4),
+            LengthUnit::Pt => dpi * (1.0 / 72.),
+            LengthUnit::Pc => dpi * (1.0 / 6.),
+            LengthUnit::Em => self.font_size,
+            LengthUnit::Ex => self.x_height(),
+            LengthUnit::Percent => return None,
+        })
+    }
+    /// resolve a length that is not along an axis. percentages refer to the normalized diagonal of the viewport
     pub fn resolve_length(&self, length: Length) -> Option<f32> {
         let scale = match length.unit {
-            LengthUnit::None => 1.0,
-            LengthUnit::Cm => self.ctx.dpi * (1.0 / 2.54),
-            LengthUnit::Em => unimplemented!(),
-            LengthUnit::Ex => unimplemented!(),
-            LengthUnit::In => self.ctx.dpi,
-            LengthUnit::Mm => self.ctx.dpi * (1.0 / 25.4),
-            LengthUnit::Pc => unimplemented!(),
-            LengthUnit::Percent => return None,
-            LengthUnit::Pt => self.ctx.dpi * (1.0 / 75.),
-            LengthUnit::Px => 1.0
+            LengthUnit::Percent => self.view_box.map(|r| 0.01 * (0.5 * r.size().square_length()).sqrt())?,
+            unit => self.unit_scale(unit)?
         };
         Some(length.num as f32 * scale)
     }
     pub fn resolve_length_along(&self, length: Length, axis: Axis) -> Option<f32> {
         let scale = match length.unit {
-            LengthUnit::None => 1.0,
-            LengthUnit::Cm => self.ctx.dpi * (1.0 / 2.54),
-            LengthUnit::Em => unimplemented!(),
-            LengthUnit::Ex => unimplemented!(),
-            LengthUnit::In => self.ctx.dpi,
-            LengthUnit::Mm => self.ctx.dpi * (1.0 / 25.4),
-            LengthUnit::Pc => unimplemented!(),
-            LengthUnit::Percent => return match axis {
-                Axis::X => self.view_box.map(|r| r.width() * 0.01),
-                Axis::Y => self.view_box.map(|r| r.height() * 0.01),
+            LengthUnit::Percent => match axis {
+                Axis::X => self.view_box.map(|r| r.width() * 0.01)?,
+                Axis::Y => self.view_box.map(|r| r.height() * 0.01)?,
             },
-            LengthUnit::Pt => self.ctx.dpi * (1.0 / 75.),
-            LengthUnit::Px => 1.0
+            unit => self.unit_scale(unit)?
         };
         Some(length.num as f32 * scale)
     }
-    pub fn apply_viewbox(&mut self, width: Option<LengthX>, height: Option<LengthY>, view_box: &Rect) {
+    /// map the view box into a viewport of the given size at the origin
+    pub fn apply_viewbox(&mut self, size: Vector2F, view_box: &Rect, aspect: PreserveAspectRatio) {
         let view_box = view_box.resolve(self);
-        let width = width.and_then(|l| l.try_resolve(self)).unwrap_or(view_box.width());
-        let height = height.and_then(|l| l.try_resolve(self)).unwrap_or(view_box.height());
-        let size = vec2f(width, height);
+
+        let scale = size * view_box.size().recip();
+        let (scale, offset) = match aspect.align {
+            None => (scale, Vector2F::zero()),
+            Some((align_x, align_y)) => {
+                let scale = Vector2F::splat(if aspect.slice {
+                    scale.x().max(scale.y())
+                } else {
+                    scale.x().min(scale.y())
+                });
+                let free = size - view_box.size() * scale;
+                (scale, vec2f(align_x.fraction() * free.x(), align_y.fraction() * free.y()))
+            }
+        };
         
-        self.apply_transform(Transform2F::from_scale(view_box.size().recip() * size) * Transform2F::from_translation(-view_box.origin()));
+        self.apply_transform(Transform2F::from_translation(offset) * Transform2F::from_scale(scale) * Transform2F::from_translation(-view_box.origin()));
         self.view_box = Some(view_box);
     }
+    /// move into the coordinate system of the viewport. does not clip.
+    pub fn apply_viewport(&mut self, viewport: &Viewport) {
+        self.apply_transform(Transform2F::from_translation(viewport.rect.origin()));
+        match viewport.view_box {
+            Some(view_box) => self.apply_viewbox(viewport.rect.size(), view_box, viewport.aspect),
+            None if viewport.has_size => self.view_box = Some(RectF::new(Vector2F::zero(), viewport.rect.size())),
+            // percentages keep referring to the enclosing viewport, if there is one
+            None => debug!("viewport without a size"),
+        }
+    }
+}
+
+/// viewport established by a nested svg or a symbol instantiated by a `<use>`
+pub struct Viewport<'i> {
+    /// position and size in the user space of the parent
+    pub rect: RectF,
+    /// false if the size could not be resolved, like for an outermost svg without width, height and viewBox
+    pub has_size: bool,
+    pub view_box: Option<&'i Rect>,
+    pub aspect: PreserveAspectRatio,
+    /// whether content outside of `rect` is clipped
+    pub clip: bool,
+}
+
+/// the visible area
+#[derive(Clone, Debug)]
+pub struct Clip {
+    /// bounds in scene coordinates, `None` if nothing is visible
+    pub rect: Option<RectF>,
+    pub path: ClipPathId,
+}
+impl Clip {
+    pub fn new(rect: Option<RectF>, path: ClipPathId) -> Clip {
+        Clip { rect, path }
+    }
+    /// the clip path id to draw with, `None` without a clip
+    pub fn id(clip: Option<&Clip>) -> Option<ClipPathId> {
+        clip.map(|clip| clip.path)
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct DrawOptions<'a> {
     pub common: Options<'a>,
-    pub clip_path: Option<(RectF, ClipPathId)>, //ClipPathAttr,
+    pub clip_path: Option<Clip>,
 }
 impl<'a> Deref for DrawOptions<'a> {
     type Target = Options<'a>;
@@ -287,7 +488,8 @@ impl<'a> DerefMut for DrawOptions<'a> {
 #[derive(Clone, Debug)]
 pub struct BoundsOptions<'a> {
     pub common: Options<'a>,
-    pub clip_rect: Option<RectF>,
+    /// bounds of the clip in scene coordinates. `None` without a clip, `Some(None)` if nothing is visible.
+    pub clip_rect: Option<Option<RectF>>,
 }
 impl<'a> Deref for BoundsOptions<'a> {
     type Target = Options<'a>;
@@ -308,16 +510,17 @@ impl<'a> BoundsOptions<'a> {
         }
     }
     pub fn apply(&self, attrs: &Attrs) -> BoundsOptions<'a> {
+        self.apply_with_bbox(attrs, |_| None)
+    }
+    /// like `apply`, but `bbox` provides the object bounding box of the element
+    /// in case a clip path needs it.
+    pub fn apply_with_bbox(&self, attrs: &Attrs, bbox: impl FnOnce(&Options<'a>) -> Option<RectF>) -> BoundsOptions<'a> {
         let common = self.common.apply(attrs);
         let clip_rect = match attrs.clip_path {
             Some(ClipPathAttr::Ref(ref id)) => {
                 if let Some(Item::ClipPath(p)) = self.ctx.resolve(id).map(|t| &**t) {
-                    let outline = p.resolve(&self);
-                    let inner_rect = outline.bounds();
-                    match self.clip_rect {
-                        None => Some(inner_rect),
-                        Some(outer_rect) => outer_rect.intersection(inner_rect),
-                    }
+                    let bbox = bbox(&common);
+                    Some(self.clip_rect(p, &common, bbox, self.clip_rect))
                 } else {
                     println!("clip path missing: {}", id);
                     None
@@ -327,6 +530,30 @@ impl<'a> BoundsOptions<'a> {
         };
         BoundsOptions { common, clip_rect }
     }
+    /// bounds of the clip path intersected with `outer`, `None` if nothing is visible
+    fn clip_rect(&self, clip: &TagClipPath, options: &Options, bbox: Option<RectF>, outer: Option<Option<RectF>>) -> Option<RectF> {
+        let outer = match clip.attrs.clip_path {
+            Some(ClipPathAttr::Ref(ref id)) => match self.ctx.resolve(id).map(|t| &**t) {
+                Some(Item::ClipPath(inner)) => Some(self.clip_rect(inner, options, bbox, outer)),
+                _ => outer
+            }
+            _ => outer
+        };
+        let outline = clip_outline(clip, options, bbox);
+        let inner_rect = match outline.len() {
+            0 => None,
+            _ => Some(outline.bounds())
+        };
+        intersect_clip(outer, inner_rect)
+    }
+    /// clip to the viewport if needed and move into its coordinate system
+    pub fn enter_viewport(&mut self, viewport: &Viewport) {
+        if viewport.clip {
+            let rect = self.transform * viewport.rect;
+            self.clip_rect = Some(intersect_clip(self.clip_rect, Some(rect)));
+        }
+        self.apply_viewport(viewport);
+    }
     pub fn bounds(&self, rect: RectF) -> Option<RectF> {
         let rect = if self.has_stroke() {
             Some(self.transform * rect.dilate(self.stroke_style.line_width))
@@ -335,14 +562,21 @@ impl<'a> BoundsOptions<'a> {
         } else {
             None
         };
-        if let Some(clip) = self.clip_rect {
-            rect.and_then(|r| r.intersection(clip))
-        } else {
-            rect
+        match self.clip_rect {
+            Some(clip) => rect.and_then(|r| r.intersection(clip?)),
+            None => rect
         }
     }
 }
 
+/// the bounds of a clip inside of an `outer` clip (if any), `None` if nothing is visible
+fn intersect_clip(outer: Option<Option<RectF>>, rect: Option<RectF>) -> Option<RectF> {
+    match outer {
+        Some(outer) => outer?.intersection(rect?),
+        None => rect
+    }
+}
+
 impl<'a> DrawOptions<'a> {
     pub fn new(ctx: &'a DrawContext<'a>) -> DrawOptions<'a> {
         DrawOptions {
@@ -359,16 +593,33 @@ impl<'a> DrawOptions<'a> {
         self.draw_transformed(scene, path, Transform2F::default());
     }
     pub fn draw_transformed(&self, scene: &mut Scene, path: &Outline, transform: Transform2F) {
+        for &layer in self.paint_order.layers() {
+            self.draw_layer(scene, path, transform, layer);
+        }
+    }
+    pub fn draw_layer(&self, scene: &mut Scene, path: &Outline, transform: Transform2F, layer: PaintLayer) {
+        if !self.is_visible() {
+            return;
+        }
+        match layer {
+            PaintLayer::Fill => self.draw_fill(scene, path, transform),
+            PaintLayer::Stroke => self.draw_stroke(scene, path, transform),
+            PaintLayer::Markers => {}
+        }
+    }
+    fn draw_fill(&self, scene: &mut Scene, path: &Outline, transform: Transform2F) {
         let tr = self.transform * transform;
-        let clip_path_id = self.clip_path.map(|(_, id)| id);
         if let Some(ref fill) = self.resolve_paint(&self.fill, self.fill_opacity) {
             let outline = path.clone().transformed(&tr);
             let paint_id = scene.push_paint(fill);
             let mut draw_path = DrawPath::new(outline, paint_id);
             draw_path.set_fill_rule(self.fill_rule);
-            draw_path.set_clip_path(clip_path_id);
+            draw_path.set_clip_path(Clip::id(self.clip_path.as_ref()));
             scene.push_draw_path(draw_path);
         }
+    }
+    fn draw_stroke(&self, scene: &mut Scene, path: &Outline, transform: Transform2F) {
+        let tr = self.transform * transform;
         if let Some(ref stroke) = self.resolve_paint(&self.stroke, self.stroke_opacity) {
             if self.stroke_style.line_width > 0. {
                 let paint_id = scene.push_paint(stroke);
@@ -381,53 +632,31 @@ impl<'a> DrawOptions<'a> {
                 }
                 let mut stroke = OutlineStrokeToFill::new(&outline, self.stroke_style);
                 stroke.offset();
-                let path = stroke.into_outline();
-                let mut draw_path = DrawPath::new(path.transformed(&tr), paint_id);
-                draw_path.set_clip_path(clip_path_id);
+                let path = stroke.into_outline().transformed(&tr);
+                let mut draw_path = DrawPath::new(path, paint_id);
+                draw_path.set_clip_path(Clip::id(self.clip_path.as_ref()));
                 scene.push_draw_path(draw_path);
             }
         }
     }
     pub fn apply(&self, scene: &mut Scene, attrs: &Attrs) -> DrawOptions<'a> {
+        self.apply_with_bbox(scene, attrs, |_| None)
+    }
+    /// like `apply`, but `bbox` provides the object bounding box of the element
+    /// in case a clip path needs it.
+    pub fn apply_with_bbox(&self, scene: &mut Scene, attrs: &Attrs, bbox: impl FnOnce(&Options<'a>) -> Option<RectF>) -> DrawOptions<'a> {
         let common = self.common.apply(attrs);
         let clip_path = match attrs.clip_path {
             Some(ClipPathAttr::Ref(ref id)) => {
                 if let Some(Item::ClipPath(p)) = self.ctx.resolve(id).map(|t| &**t) {
-                    let outline = p.resolve(&common);
-                    let clip_rect = outline.bounds();
-                    println!("{:?}, {:?}, {:?}", p, outline, clip_rect);
-                    // begin debug
-                    /*
-                    let paint = PaPaint::from_color(ColorU::new(255, 0, 255, 127));
-                    let paint_id = scene.push_paint(&paint);
-                    
-                    let draw_path = DrawPath::new(outline.clone(), paint_id);
-                    scene.push_draw_path(draw_path);
-                    */
-                    // end debug
-
-                    let push_clip_path = |id: Option<ClipPathId>| {
-                        let mut clip_path = ClipPath::new(outline);
-                        clip_path.set_fill_rule(self.clip_rule);
-                        clip_path.set_clip_path(id);
-                        scene.push_clip_path(clip_path)
-                    };
-
-                    if let Some((rect, id)) = self.clip_path {
-                        if let Some(intersection) = rect.intersection(clip_rect) {
-                            Some((intersection, push_clip_path(Some(id))))
-                        } else {
-                            None
-                        }
-                    } else {
-                        Some((clip_rect, push_clip_path(None)))
-                    }
+                    let bbox = bbox(&common);
+                    Some(self.push_clip_path(scene, p, &common, bbox, self.clip_path.clone()))
                 } else {
                     println!("clip path missing: {}", id);
                     None
                 }
             }
-            _ => self.clip_path,
+            _ => self.clip_path.clone(),
         };
 
         debug!("fill {:?} + {:?} -> {:?}", self.fill, attrs.fill, common.fill);
@@ -435,10 +664,117 @@ impl<'a> DrawOptions<'a> {
         
         DrawOptions { common, clip_path }
     }
+    /// pushes the clip path, intersected with `outer` and the clip path set on the clipPath element itself
+    fn push_clip_path(&self, scene: &mut Scene, clip: &TagClipPath, options: &Options, bbox: Option<RectF>, outer: Option<Clip>) -> Clip {
+        let outer = match clip.attrs.clip_path {
+            Some(ClipPathAttr::Ref(ref id)) => match self.ctx.resolve(id).map(|t| &**t) {
+                Some(Item::ClipPath(inner)) => Some(self.push_clip_path(scene, inner, options, bbox, outer)),
+                _ => outer
+            }
+            _ => outer
+        };
+        let outline = clip_outline(clip, options, bbox);
+        let clip_rect = match outline.len() {
+            0 => None,
+            _ => Some(outline.bounds())
+        };
+        let rect = intersect_clip(outer.as_ref().map(|outer| outer.rect), clip_rect);
+
+        let mut clip_path = ClipPath::new(outline);
+        clip_path.set_fill_rule(FillRule::Winding);
+        clip_path.set_clip_path(Clip::id(outer.as_ref()));
+        Clip::new(rect, scene.push_clip_path(clip_path))
+    }
+    /// clip to the viewport if needed and move into its coordinate system
+    pub fn enter_viewport(&mut self, scene: &mut Scene, viewport: &Viewport) {
+        if viewport.clip {
+            let outline = Outline::from_rect(viewport.rect).transformed(&self.transform);
+            let outer = self.clip_path.take();
+            let rect = intersect_clip(outer.as_ref().map(|outer| outer.rect), Some(outline.bounds()));
+            let mut clip_path = ClipPath::new(outline);
+            clip_path.set_clip_path(Clip::id(outer.as_ref()));
+            self.clip_path = Some(Clip::new(rect, scene.push_clip_path(clip_path)));
+        }
+        self.apply_viewport(viewport);
+    }
     pub fn bounds_options(&self) -> BoundsOptions<'a> {
         BoundsOptions {
             common: self.common.clone(),
-            clip_rect: self.clip_path.map(|(rect, _)| rect)
+            clip_rect: self.clip_path.as_ref().map(|clip| clip.rect)
         }
     }
 }
+
+#[test]
+fn test_units() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg"/>"#).unwrap();
+    let mut ctx = DrawContext::new_without_fonts(&svg);
+    ctx.set_dpi(96.);
+    let mut options = Options::new(&ctx);
+    options.font_size = 16.;
+    let close = |length: Option<f32>, expected: f32| (length.unwrap() - expected).abs() < 1e-3;
+    let length = |num, unit| Length::new(num, unit);
+
+    assert!(close(options.resolve_length(length(1., LengthUnit::In)), 96.));
+    assert!(close(options.resolve_length(length(2.54, LengthUnit::Cm)), 96.));
+    assert!(close(options.resolve_length(length(25.4, LengthUnit::Mm)), 96.));
+    assert!(close(options.resolve_length(length(72., LengthUnit::Pt)), 96.));
+    assert!(close(options.resolve_length(length(6., LengthUnit::Pc)), 96.));
+    assert!(close(options.resolve_length(length(2., LengthUnit::Em)), 32.));
+    // without fonts an ex is half an em
+    assert!(close(options.resolve_length(length(2., LengthUnit::Ex)), 16.));
+
+    // percentages need a viewport
+    assert_eq!(options.resolve_length_along(length(50., LengthUnit::Percent), Axis::X), None);
+    options.view_box = Some(RectF::new(Vector2F::zero(), vec2f(300., 400.)));
+    assert_eq!(options.resolve_length_along(length(50., LengthUnit::Percent), Axis::X), Some(150.));
+    assert_eq!(options.resolve_length_along(length(50., LengthUnit::Percent), Axis::Y), Some(200.));
+    assert!(close(options.resolve_length(length(100., LengthUnit::Percent)), (0.5f32 * (300. * 300. + 400. * 400.)).sqrt()));
+}
+
+#[test]
+fn test_unresolved_lengths_inherit() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect id="r" stroke-width="10%" font-size="50%" letter-spacing="1em"/>
+        <rect id="s" font-size="200%" stroke-width="10%"/>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let mut options = Options::new(&ctx);
+    options.stroke_style.line_width = 3.;
+    options.font_size = 16.;
+    let attrs = |id| match **svg.get_item(id).unwrap() {
+        Item::Rect(ref rect) => &rect.attrs,
+        _ => unreachable!()
+    };
+    // there is no viewport to resolve the stroke width against,
+    // the font size and spacing are relative to the font size of the parent
+    let applied = options.apply(attrs("r"));
+    assert_eq!(applied.stroke_style.line_width, 3.);
+    assert_eq!(applied.font_size, 8.);
+    assert_eq!(applied.letter_spacing, 8.);
+
+    // a viewport doesn't change the percentage of the font size
+    options.view_box = Some(RectF::new(Vector2F::zero(), vec2f(100., 100.)));
+    let applied = options.apply(attrs("s"));
+    assert_eq!(applied.font_size, 32.);
+    assert!((applied.stroke_style.line_width - 10.).abs() < 1e-3);
+}
+
+#[test]
+fn test_kerning_length() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <text id="t" kerning="2" letter-spacing="1" word-spacing="0.5em">a</text>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let mut options = Options::new(&ctx);
+    options.font_size = 10.;
+    let attrs = match **svg.get_item("t").unwrap() {
+        Item::Text(ref text) => &text.attrs,
+        _ => unreachable!()
+    };
+    // a length disables kerning and adds to the letter spacing
+    let options = options.apply(attrs);
+    assert!(!options.font_kerning);
+    assert_eq!(options.letter_spacing, 3.);
+    assert_eq!(options.word_spacing, 5.);
+}
@@ -0,0 +1,112 @@
+use crate::prelude::*;
+use crate::filter::{apply_filter, Layer};
+use crate::mask::draw_mask;
+use pathfinder_renderer::paint::Paint;
+use pathfinder_content::effects::BlendMode;
+
+/// An element that carries presentation attributes.
+/// `draw_element` and `element_bounds` apply the attributes (clip path, filter, mask, opacity, blend mode)
+/// the same way for every element, so implementations only deal with their content.
+pub trait Element {
+    fn attrs(&self) -> &Attrs;
+
+    /// object bounding box in the user space of `options`, which have the attributes applied
+    fn bbox(&self, options: &Options) -> Option<RectF>;
+
+    /// extent of the drawn content in scene coordinates
+    fn content_bounds(&self, options: &BoundsOptions) -> Option<RectF>;
+
+    /// whether parts of the content can overlap, so that opacity can't be applied to each part
+    fn overlaps(&self, options: &Options) -> bool;
+
+    fn draw_content(&self, scene: &mut Scene, options: &DrawOptions);
+}
+
+impl<E: Element> DrawItem for E {
+    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
+        draw_element(self, scene, options)
+    }
+    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
+        element_bounds(self, options)
+    }
+}
+
+pub fn element_bounds(element: &impl Element, options: &BoundsOptions) -> Option<RectF> {
+    let attrs = element.attrs();
+    if !attrs.display.resolve(options) {
+        return None;
+    }
+    let options = options.apply_with_bbox(attrs, |o| element.bbox(o));
+    element.content_bounds(&options)
+}
+
+pub fn draw_element(element: &impl Element, scene: &mut Scene, options: &DrawOptions) {
+    let attrs = element.attrs();
+    if !attrs.display.resolve(options) {
+        return;
+    }
+    let options = options.apply_with_bbox(scene, attrs, |o| element.bbox(o));
+
+    let mask = match attrs.mask {
+        Some(Iri(ref mask_id)) => match options.ctx.resolve(mask_id).map(|i| &**i) {
+            Some(Item::Mask(mask)) => Some(mask),
+            r => {
+                println!("expected mask for {:?}, got {:?}", mask_id, r);
+                None
+            }
+        }
+        None => None
+    };
+
+    let isolate = mask.is_some()
+        || attrs.isolation
+        || !matches!(options.blend_mode, BlendMode::SrcOver)
+        || (options.opacity < 1.0 && element.overlaps(&options));
+
+    if !isolate {
+        draw_filtered(element, scene, &options);
+        return;
+    }
+
+    // the element is blended as a whole, never its fill and stroke separately
+    let bounds = match element.content_bounds(&options.bounds_options()).or_else(|| options.clip_path.as_ref()?.rect) {
+        Some(bounds) => bounds,
+        None => {
+            // without bounds there is no layer to draw into, so draw directly
+            debug!("no bounds for isolated content, drawing without a layer");
+            let mut direct = options.clone();
+            direct.blend_mode = BlendMode::SrcOver;
+            draw_filtered(element, scene, &direct);
+            return;
+        }
+    };
+    let mut inner = options.clone();
+    inner.opacity = 1.0;
+    inner.blend_mode = BlendMode::SrcOver;
+    let layer = Layer::push(scene, bounds, &mut inner);
+
+    draw_filtered(element, scene, &inner);
+    if let Some(mask) = mask {
+        draw_mask(mask, scene, &inner, layer.area(), element.bbox(&inner));
+    }
+
+    let mut paint = Paint::from_pattern(layer.pattern());
+    paint.set_opacity(options.opacity);
+    layer.composite(scene, paint, options.blend_mode, options.clip_path.as_ref());
+}
+
+fn draw_filtered(element: &impl Element, scene: &mut Scene, options: &DrawOptions) {
+    if let Some(Iri(ref filter_id)) = element.attrs().filter {
+        match options.ctx.resolve(filter_id).map(|i| &**i) {
+            Some(Item::Filter(filter)) => {
+                let bounds = get_or_return!(element.content_bounds(&options.bounds_options()));
+                apply_filter(filter, scene, options, bounds, |scene, options| {
+                    element.draw_content(scene, options)
+                });
+                return;
+            },
+            r => println!("expected filter for {:?}, got {:?}", filter_id, r)
+        }
+    }
+    element.draw_content(scene, options);
+}
@@ -20,19 +20,21 @@ fn ellipse_outline(tag: &TagEllipse, options: &Options) -> Option<Outline> {
 
 impl Shape for TagEllipse {
     fn outline(&self, options: &Options) -> Option<Outline> {
-        if !self.attrs.display {
+        if !self.attrs.display.resolve(options) {
             return None;
         }
         let options = options.apply(&self.attrs);
         ellipse_outline(self, &options).map(|o| o.transformed(options.get_transform()))
     }
 }
-impl DrawItem for TagEllipse {
-    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
-        if !self.attrs.display {
-            return None;
-        }
-        let options = options.apply(&self.attrs);
+impl Element for TagEllipse {
+    fn attrs(&self) -> &Attrs {
+        &self.attrs
+    }
+    fn bbox(&self, options: &Options) -> Option<RectF> {
+        ellipse_outline(self, options).map(|o| o.bounds())
+    }
+    fn content_bounds(&self, options: &BoundsOptions) -> Option<RectF> {
         let center = self.center.resolve(&options);
         let radius = self.radius.resolve(&options);
 
@@ -42,13 +44,10 @@ impl DrawItem for TagEllipse {
 
         options.bounds(RectF::new(center - radius, radius * 2.0))
     }
-
-    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        if !self.attrs.display {
-            return;
-        }
-        let options = options.apply(scene, &self.attrs);
-
+    fn overlaps(&self, options: &Options) -> bool {
+        options.has_fill() && options.has_stroke()
+    }
+    fn draw_content(&self, scene: &mut Scene, options: &DrawOptions) {
         if let Some(outline) = ellipse_outline(self, &options) {
             options.draw(scene, &outline);
         }
@@ -74,19 +73,21 @@ fn circle_outline(tag: &TagCircle, options: &Options) -> Option<Outline> {
 }
 impl Shape for TagCircle {
     fn outline(&self, options: &Options) -> Option<Outline> {
-        if !self.attrs.display {
+        if !self.attrs.display.resolve(options) {
             return None;
         }
         let options = options.apply(&self.attrs);
         circle_outline(self, &options).map(|o| o.transformed(options.get_transform()))
     }
 }
-impl DrawItem for TagCircle {
-    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
-        if !self.attrs.display {
-            return None;
-        }
-        let options = options.apply(&self.attrs);
+impl Element for TagCircle {
+    fn attrs(&self) -> &Attrs {
+        &self.attrs
+    }
+    fn bbox(&self, options: &Options) -> Option<RectF> {
+        circle_outline(self, options).map(|o| o.bounds())
+    }
+    fn content_bounds(&self, options: &BoundsOptions) -> Option<RectF> {
         let center = self.center.resolve(&options);
         let radius = self.radius.resolve(&options);
 
@@ -97,15 +98,12 @@ impl DrawItem for TagCircle {
         let radius = Vector2F::splat(radius);
         options.bounds(RectF::new(center - radius, radius * 2.0))
     }
-
-    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        if !self.attrs.display {
-            return;
-        }
-        let options = options.apply(scene, &self.attrs);
-
+    fn overlaps(&self, options: &Options) -> bool {
+        options.has_fill() && options.has_stroke()
+    }
+    fn draw_content(&self, scene: &mut Scene, options: &DrawOptions) {
         if let Some(outline) = circle_outline(self, &options) {
             options.draw(scene, &outline);
         }
     }
-}
\ No newline at end of file
+}
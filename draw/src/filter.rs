@@ -11,11 +11,17 @@ use pathfinder_content::{
 };
 use pathfinder_geometry::rect::RectI;
 use pathfinder_color::matrix::ColorMatrix;
+use pathfinder_simd::default::F32x4;
+use crate::paint::encode_srgb;
 
 pub fn apply_filter(filter: &TagFilter, scene: &mut Scene, options: &DrawOptions, bounds: RectF, f: impl FnOnce(&mut Scene, &DrawOptions)) {
     if let Some(first) = filter.filters.first() {
         let mut options2 = options.clone();
-        let info = FilterState::pre(first, scene, bounds, &mut options2);
+        // like the filter element, which inherits from its own ancestors
+        let color_interpolation = first.color_interpolation_filters
+            .or(filter.color_interpolation_filters)
+            .unwrap_or(options.color_interpolation_filters);
+        let info = FilterState::pre(&first.filter, scene, bounds, &mut options2, color_interpolation);
         f(scene, &options2);
         info.post(scene, options);
     } else {
@@ -28,9 +34,14 @@ enum FilterState {
     ColorMatrix(ColorMatrixInfo),
 }
 impl FilterState {
-    fn pre(filter: &Filter, scene: &mut Scene, outline_bounds: RectF, options: &mut DrawOptions) -> FilterState {
+    fn pre(filter: &Filter, scene: &mut Scene, outline_bounds: RectF, options: &mut DrawOptions, color_interpolation: ColorInterpolation) -> FilterState {
         match *filter {
+            // patterns are blurred as they are stored, in sRGB. no pattern filter can convert them
+            // to linearRGB and back, so `color-interpolation-filters` has no effect on the blur.
             Filter::GaussianBlur(ref f) => {
+                if color_interpolation == ColorInterpolation::LinearRGB {
+                    debug!("feGaussianBlur is not supported in linearRGB, blurring in sRGB");
+                }
                 let sigma = options.transform.extract_scale() * f.std_deviation;
                 let bounds = outline_bounds.dilate(sigma * 3.0).round_out().to_i32();
         
@@ -49,15 +60,11 @@ impl FilterState {
                 })
             }
             Filter::ColorMatrix(filter) => {
-                let bounds = outline_bounds.round_out().to_i32();
-                let render_target = RenderTarget::new(bounds.size(), String::new());
-                let render_target_id = scene.push_render_target(render_target);
-                options.transform = Transform2F::from_translation(-bounds.origin().to_f32()) * options.transform;
-
+                let layer = Layer::push(scene, outline_bounds, options);
                 FilterState::ColorMatrix(ColorMatrixInfo {
-                    render_target_id,
-                    bounds,
-                    filter, 
+                    layer,
+                    filter,
+                    linear: color_interpolation == ColorInterpolation::LinearRGB,
                 })
             }
         }
@@ -97,28 +104,22 @@ impl FilterState {
             }
             FilterState::ColorMatrix(info) => {
                 let ColorMatrixInfo {
-                    render_target_id,
-                    bounds,
-                    filter
+                    layer,
+                    filter,
+                    linear
                 } = info;
 
-                let mut paint = Pattern::from_render_target(render_target_id, bounds.size());
+                let mut pattern = layer.pattern();
                 let (matrix, blend_mode) = match filter {
                     FeColorMatrix::Matrix(columns) => (ColorMatrix(columns), BlendMode::SrcOver),
                     FeColorMatrix::Saturate(saturation) => (ColorMatrix::saturate(saturation), BlendMode::SrcOver),
                     FeColorMatrix::HueRotate(radians) => (ColorMatrix::hue_rotate(radians), BlendMode::SrcOver),
                     FeColorMatrix::LuminanceToAlpha => (ColorMatrix::luminance_to_alpha(), BlendMode::DestIn),
                 };
-                paint.set_filter(Some(PatternFilter::ColorMatrix(matrix)));
-                paint.apply_transform(Transform2F::from_translation(bounds.origin().to_f32()));
+                let matrix = if linear { linear_matrix(matrix) } else { matrix };
+                pattern.set_filter(Some(PatternFilter::ColorMatrix(matrix)));
 
-                let paint_id = scene.push_paint(&Paint::from_pattern(paint));
-                let outline = Outline::from_rect(bounds.to_f32());
-                let mut path = DrawPath::new(outline, paint_id);
-                path.set_blend_mode(blend_mode);
-
-                scene.pop_render_target();
-                scene.push_draw_path(path);
+                layer.composite(scene, Paint::from_pattern(pattern), blend_mode, options.clip_path.as_ref());
             }
         }
     }
@@ -131,7 +132,81 @@ struct GaussianBlurInfo {
     render_target_id_x: RenderTargetId,
 }
 struct ColorMatrixInfo {
-    bounds: RectI,
-    render_target_id: RenderTargetId,
+    layer: Layer,
     filter: FeColorMatrix,
-}
\ No newline at end of file
+    linear: bool,
+}
+
+/// adapts a matrix meant for linearRGB values to sRGB values, since patterns are filtered in sRGB.
+/// the result is exact for black and for each input channel at full intensity.
+fn linear_matrix(ColorMatrix(columns): ColorMatrix) -> ColorMatrix {
+    let offset = columns[4];
+    let encoded_offset = |row: usize| encode_srgb(offset[row]);
+    let column = |c: F32x4| F32x4::new(
+        encode_srgb(c[0] + offset[0]) - encoded_offset(0),
+        encode_srgb(c[1] + offset[1]) - encoded_offset(1),
+        encode_srgb(c[2] + offset[2]) - encoded_offset(2),
+        c[3],
+    );
+    ColorMatrix([
+        column(columns[0]),
+        column(columns[1]),
+        column(columns[2]),
+        column(columns[3]),
+        F32x4::new(encoded_offset(0), encoded_offset(1), encoded_offset(2), offset[3]),
+    ])
+}
+
+/// offscreen render target covering a region of the scene
+pub struct Layer {
+    render_target_id: RenderTargetId,
+    bounds: RectI,
+}
+impl Layer {
+    /// push a render target covering `bounds` and move `options` into its coordinate system
+    pub fn push(scene: &mut Scene, bounds: RectF, options: &mut DrawOptions) -> Layer {
+        let bounds = bounds.round_out().to_i32();
+        let render_target = RenderTarget::new(bounds.size(), String::new());
+        let render_target_id = scene.push_render_target(render_target);
+        options.transform = Transform2F::from_translation(-bounds.origin().to_f32()) * options.transform;
+
+        // clip paths live in scene coordinates and are applied when compositing
+        options.clip_path = None;
+
+        Layer { render_target_id, bounds }
+    }
+
+    /// pattern that shows the content of the layer at its position in the scene
+    pub fn pattern(&self) -> Pattern {
+        let mut pattern = Pattern::from_render_target(self.render_target_id, self.bounds.size());
+        pattern.apply_transform(Transform2F::from_translation(self.bounds.origin().to_f32()));
+        pattern
+    }
+
+    /// area of the layer in its own coordinate system
+    pub fn area(&self) -> RectF {
+        RectF::new(Vector2F::zero(), self.bounds.size().to_f32())
+    }
+
+    /// pop the render target and draw its area with `paint`
+    pub fn composite(self, scene: &mut Scene, paint: Paint, blend_mode: BlendMode, clip: Option<&Clip>) {
+        scene.pop_render_target();
+
+        let paint_id = scene.push_paint(&paint);
+        let outline = Outline::from_rect(self.bounds.to_f32());
+        let mut path = DrawPath::new(outline, paint_id);
+        path.set_blend_mode(blend_mode);
+        path.set_clip_path(Clip::id(clip));
+        scene.push_draw_path(path);
+    }
+}
+
+#[test]
+fn test_linear_color_matrix() {
+    // the gray level of a full red after desaturation
+    let red = |ColorMatrix(columns): ColorMatrix| columns[0][0] + columns[4][0];
+    assert!((red(ColorMatrix::saturate(0.0)) - 0.2126).abs() < 1e-4);
+    // in linearRGB the luminance is computed on linear values and encoded again, which is lighter
+    assert!((red(linear_matrix(ColorMatrix::saturate(0.0))) - encode_srgb(0.2126)).abs() < 1e-4);
+    assert!(red(linear_matrix(ColorMatrix::saturate(0.0))) > 0.45);
+}
@@ -1,85 +1,192 @@
 use crate::prelude::*;
 use std::sync::Arc;
-use crate::filter::apply_filter;
+use crate::effects::Element;
+use crate::svg::svg_viewport;
+use crate::path::{item_outline, object_bounds};
+use pathfinder_content::outline::Outline;
 
-impl DrawItem for TagG {
-    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
-        if !self.attrs.display {
-            return None;
+/// union of the outlines of the items
+fn items_outline(items: &[Arc<Item>], options: &Options) -> Option<Outline> {
+    let mut outline = Outline::new();
+    for item in items {
+        if let Some(o) = item_outline(item, options) {
+            outline.push_outline(o);
         }
+    }
+    Some(outline)
+}
 
+impl Shape for TagG {
+    fn outline(&self, options: &Options) -> Option<Outline> {
+        if !self.attrs.display.resolve(options) {
+            return None;
+        }
         let options = options.apply(&self.attrs);
-        max_bounds(self.items.iter().flat_map(|item| item.bounds(&options)))
-    }
-    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        draw_items(scene, &self.items, &self.attrs, options);
+        items_outline(&self.items, &options)
     }
 }
 
-fn draw_items(scene: &mut Scene, items: &[Arc<Item>], attrs: &Attrs, options: &DrawOptions) {
-    if !attrs.display {
-        return;
+/// the children of a container, drawn with the attributes of the container
+struct Group<'i> {
+    items: &'i [Arc<Item>],
+    attrs: &'i Attrs,
+}
+impl<'i> Element for Group<'i> {
+    fn attrs(&self) -> &Attrs {
+        self.attrs
     }
-
-    let options = options.apply(scene, attrs);
-
-    if let Some(Iri(ref filter_id)) = attrs.filter {
-        let bounds_options = options.bounds_options();
-        let bounds = get_or_return!(max_bounds(items.iter().flat_map(|item| item.bounds(&bounds_options))));
-
-        match options.ctx.resolve(&filter_id).map(|i| &**i) {
-            Some(Item::Filter(filter)) => {
-                apply_filter(filter, scene, &options, bounds, |scene, options| {
-                    for item in items {
-                        item.as_ref().draw_to(scene, options);
-                    }
-                });
-                return;
-            },
-            r => println!("expected filter for {:?}, got {:?}", filter_id, r)
+    fn bbox(&self, options: &Options) -> Option<RectF> {
+        object_bounds(self.items.iter().map(|i| &**i), options)
+    }
+    fn content_bounds(&self, options: &BoundsOptions) -> Option<RectF> {
+        max_bounds(self.items.iter().flat_map(|item| item.bounds(options)))
+    }
+    fn overlaps(&self, options: &Options) -> bool {
+        needs_layer(self.items, options)
+    }
+    fn draw_content(&self, scene: &mut Scene, options: &DrawOptions) {
+        for item in self.items.iter() {
+            item.draw_to(scene, options);
         }
     }
+}
 
-    for item in items.iter() {
-        item.draw_to(scene, &options);
+impl DrawItem for TagG {
+    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
+        Group { items: &self.items, attrs: &self.attrs }.bounds(options)
+    }
+    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
+        Group { items: &self.items, attrs: &self.attrs }.draw_to(scene, options)
     }
 }
-fn content_transform<'a>(tag: &TagUse, options: &mut Options<'a>, item: &Item) {
+
+/// Group opacity can only be folded into the paint of the children if they can't overlap.
+/// That is the case for a single child that is either filled or stroked.
+pub fn needs_layer(items: &[Arc<Item>], options: &Options) -> bool {
+    let attrs = match items {
+        [] => return false,
+        [item] => match **item {
+            Item::Path(TagPath { ref attrs, .. }) |
+            Item::Rect(TagRect { ref attrs, .. }) |
+            Item::Polygon(TagPolygon { ref attrs, .. }) |
+            Item::Polyline(TagPolyline { ref attrs, .. }) |
+            Item::Line(TagLine { ref attrs, .. }) |
+            Item::Ellipse(TagEllipse { ref attrs, .. }) |
+            Item::Circle(TagCircle { ref attrs, .. }) => attrs,
+            _ => return true
+        },
+        _ => return true
+    };
+    let options = options.apply(attrs);
+    options.has_fill() && options.has_stroke()
+}
+/// translate `options` by x/y of the `<use>` and return the viewport
+/// of the referenced symbol or svg, if it is one
+fn content_transform<'i>(tag: &TagUse, options: &mut Options, item: &'i Item) -> Option<Viewport<'i>> {
     let pos = tag.pos.resolve(&options);
     options.apply_transform(Transform2F::from_translation(pos));
+
+    // width and height of the `<use>` override those of the referenced element
+    let size = |default: Vector2F| vec2f(
+        tag.width.and_then(|w| w.try_resolve(options)).unwrap_or(default.x()),
+        tag.height.and_then(|h| h.try_resolve(options)).unwrap_or(default.y()),
+    );
     match *item {
-        Item::Symbol(TagSymbol { view_box: Some(ref view_box), .. }) |
-        Item::Svg(TagSvg { view_box: Some(ref view_box), .. }) => {
-            options.apply_viewbox(tag.width, tag.height, view_box);
+        Item::Symbol(ref symbol) => {
+            let full = Length::new(100., LengthUnit::Percent);
+            let default = vec2f(
+                LengthX(full).try_resolve(options).unwrap_or(0.0),
+                LengthY(full).try_resolve(options).unwrap_or(0.0),
+            );
+            Some(Viewport {
+                rect: RectF::new(Vector2F::zero(), size(default)),
+                has_size: true,
+                view_box: symbol.view_box.as_ref(),
+                aspect: symbol.preserve_aspect_ratio,
+                clip: symbol.attrs.overflow != Some(Overflow::Visible),
+            })
+        }
+        Item::Svg(ref svg) => {
+            let viewport = svg_viewport(svg, options);
+            Some(Viewport {
+                rect: RectF::new(viewport.rect.origin(), size(viewport.rect.size())),
+                .. viewport
+            })
         }
-        _ => {}
+        _ => None
     }
 }
 
-impl DrawItem for TagUse {
-    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
-        if !self.attrs.display {
+/// outline of the referenced content, `options` already has the attributes of the `<use>` applied
+fn use_outline(tag: &TagUse, options: &Options) -> Option<Outline> {
+    let mut options = options.clone();
+    let item = &**options.ctx.resolve_href(tag.href.as_ref()?)?;
+    if let Some(viewport) = content_transform(tag, &mut options, item) {
+        options.apply_viewport(&viewport);
+    }
+    match *item {
+        Item::Symbol(TagSymbol { ref items, ref attrs, .. }) |
+        Item::Svg(TagSvg { ref items, ref attrs, .. }) => {
+            items_outline(items, &options.apply(attrs))
+        }
+        ref item => item_outline(item, &options)
+    }
+}
+
+impl Shape for TagUse {
+    fn outline(&self, options: &Options) -> Option<Outline> {
+        if !self.attrs.display.resolve(options) {
             return None;
         }
-        let mut options = options.apply(&self.attrs);
+        use_outline(self, &options.apply(&self.attrs))
+    }
+}
+
+fn use_bounds(tag: &TagUse, options: &Options) -> Option<RectF> {
+    let mut options = options.clone();
+    options.set_transform(Transform2F::default());
+    use_outline(tag, &options).filter(|o| o.len() > 0).map(|o| o.bounds())
+}
+
+impl Element for TagUse {
+    fn attrs(&self) -> &Attrs {
+        &self.attrs
+    }
+    fn bbox(&self, options: &Options) -> Option<RectF> {
+        use_bounds(self, options)
+    }
+    fn content_bounds(&self, options: &BoundsOptions) -> Option<RectF> {
+        let mut options = options.clone();
         let item = &**options.ctx.resolve_href(self.href.as_ref()?)?;
-        content_transform(self, &mut options, item);
-        item.bounds(&options)
+        if let Some(viewport) = content_transform(self, &mut options, item) {
+            options.enter_viewport(&viewport);
+        }
+        match *item {
+            Item::Symbol(TagSymbol { ref items, ref attrs, .. }) |
+            Item::Svg(TagSvg { ref items, ref attrs, .. }) => {
+                Group { items, attrs }.bounds(&options)
+            }
+            ref item => item.bounds(&options)
+        }
     }
-    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        if !self.attrs.display {
-            return;
+    fn overlaps(&self, options: &Options) -> bool {
+        match self.href.as_ref().and_then(|href| options.ctx.resolve_href(href)) {
+            Some(item) => needs_layer(std::slice::from_ref(item), options),
+            None => false
         }
-        let mut options = options.apply(scene, &self.attrs);
+    }
+    fn draw_content(&self, scene: &mut Scene, options: &DrawOptions) {
+        let mut options = options.clone();
         let href = get_ref_or_return!(self.href, "<use> without href");
         let item = get_or_return!(options.ctx.resolve_href(href), "can't resolve <use href={:?}>", href);
-        content_transform(&self, &mut options, item);
+        if let Some(viewport) = content_transform(&self, &mut options, item) {
+            options.enter_viewport(scene, &viewport);
+        }
         debug!("item: {:?}", *item);
         match **item {
             Item::Symbol(TagSymbol { ref items, ref attrs, .. }) |
-            Item::Svg(TagSvg { ref items, ref attrs, .. }) |
-            Item::G(TagG { ref items, ref attrs, ..}) => {
-                draw_items(scene, &items, attrs, &options);
+            Item::Svg(TagSvg { ref items, ref attrs, .. }) => {
+                Group { items, attrs }.draw_to(scene, &options);
             }
             ref item => {
                 item.draw_to(scene, &options);
@@ -3,13 +3,14 @@ use pathfinder_content::gradient::{Gradient};
 use pathfinder_color::{ColorU};
 use pathfinder_geometry::line_segment::LineSegment2F;
 use pathfinder_simd::default::F32x2;
-use svgtypes::Color;
+use crate::paint::{srgb_to_linear, linear_to_srgb};
 
 struct PartialLinearGradient<'a> {
     from: (Option<LengthX>, Option<LengthY>),
     to: (Option<LengthX>, Option<LengthY>),
     gradient_transform: Option<Transform2F>,
     stops: &'a [TagStop],
+    color_interpolation: Option<ColorInterpolation>,
 }
 
 struct PartialRadialGradient<'a> {
@@ -18,6 +19,7 @@ struct PartialRadialGradient<'a> {
     radius: Option<Length>,
     gradient_transform: Option<Transform2F>,
     stops: &'a [TagStop],
+    color_interpolation: Option<ColorInterpolation>,
 }
 
 pub trait BuildGradient {
@@ -33,7 +35,8 @@ impl BuildGradient for TagLinearGradient {
                         from: merge_point(&self.from, &other.from),
                         to: merge_point(&self.to, &other.to),
                         gradient_transform: self.gradient_transform.or(other.gradient_transform),
-                        stops: select_stops(&self.stops, &other.stops)
+                        stops: select_stops(&self.stops, &other.stops),
+                        color_interpolation: self.color_interpolation.or(other.color_interpolation),
                     }.build(options, opacity)
                 },
                 Item::RadialGradient(other) => {
@@ -41,7 +44,8 @@ impl BuildGradient for TagLinearGradient {
                         from: self.from,
                         to: self.to,
                         gradient_transform: self.gradient_transform,
-                        stops: select_stops(&self.stops, &other.stops)
+                        stops: select_stops(&self.stops, &other.stops),
+                        color_interpolation: self.color_interpolation.or(other.color_interpolation),
                     }.build(options, opacity)
                 },
                 _ => {}
@@ -52,11 +56,41 @@ impl BuildGradient for TagLinearGradient {
             from: self.from,
             to: self.to,
             gradient_transform: self.gradient_transform,
-            stops: &self.stops
+            stops: &self.stops,
+            color_interpolation: self.color_interpolation,
         }.build(options, opacity)
     }
 }
 
+/// adds the stops to the gradient. pathfinder interpolates gradients in sRGB,
+/// so linearRGB is approximated by intermediate stops.
+///
+/// `color_interpolation` is the value of the gradient element (or its `href` chain),
+/// not of the painted element.
+fn add_stops(gradient: &mut Gradient, stops: &[TagStop], opacity: f32, color_interpolation: Option<ColorInterpolation>) {
+    let linear = color_interpolation.unwrap_or(ColorInterpolation::SRGB) == ColorInterpolation::LinearRGB;
+    const STEPS: usize = 8;
+
+    let stop_color = |stop: &TagStop| Color { alpha: stop.color.alpha * stop.opacity, .. stop.color.clone() };
+    let mut prev: Option<&TagStop> = None;
+    for stop in stops {
+        match prev {
+            Some(prev) if linear && prev.offset < stop.offset => {
+                let from = srgb_to_linear(&stop_color(prev));
+                let to = srgb_to_linear(&stop_color(stop));
+                for i in 1 .. STEPS {
+                    let x = i as f32 / STEPS as f32;
+                    let color = linear_to_srgb(&from.clone().lerp(to.clone(), x));
+                    gradient.add_color_stop(color.color_u(opacity), prev.offset.lerp(stop.offset, x));
+                }
+            }
+            _ => {}
+        }
+        gradient.add_color_stop(stop.color_u(opacity), stop.offset);
+        prev = Some(stop);
+    }
+}
+
 fn select_stops<'a>(a: &'a [TagStop], b: &'a [TagStop]) -> &'a [TagStop] {
     if a.len() > 0 {
         a
@@ -97,7 +131,8 @@ impl BuildGradient for TagRadialGradient {
                         focus: merge_point(&self.focus, &other.focus),
                         radius: self.radius.or(other.radius),
                         gradient_transform: self.gradient_transform.or(other.gradient_transform),
-                        stops: select_stops(&self.stops, &other.stops)
+                        stops: select_stops(&self.stops, &other.stops),
+                        color_interpolation: self.color_interpolation.or(other.color_interpolation),
                     }.build(options, opacity)
                 }
                 Item::LinearGradient(ref other) => {
@@ -106,7 +141,8 @@ impl BuildGradient for TagRadialGradient {
                         focus: self.focus,
                         radius: self.radius,
                         gradient_transform: self.gradient_transform,
-                        stops: select_stops(&self.stops, &other.stops)
+                        stops: select_stops(&self.stops, &other.stops),
+                        color_interpolation: self.color_interpolation.or(other.color_interpolation),
                     }.build(options, opacity)
                 }
                 _ => {}
@@ -117,7 +153,8 @@ impl BuildGradient for TagRadialGradient {
             focus: self.focus,
             radius: self.radius,
             gradient_transform: self.gradient_transform,
-            stops: &self.stops
+            stops: &self.stops,
+            color_interpolation: self.color_interpolation,
         }.build(options, opacity)
     }
 }
@@ -132,9 +169,7 @@ impl<'a> PartialLinearGradient<'a> {
             from.resolve(options),
             to.resolve(options),
         );
-        for stop in self.stops {
-            gradient.add_color_stop(stop.color_u(opacity), stop.offset);
-        }
+        add_stops(&mut gradient, self.stops, opacity, self.color_interpolation);
 
         gradient.apply_transform(options.transform * gradient_transform);
         gradient
@@ -154,11 +189,28 @@ impl<'a> PartialRadialGradient<'a> {
             ),
             F32x2::new(0.0, options.resolve_length(radius).unwrap())
         );
-        for stop in self.stops {
-            gradient.add_color_stop(stop.color_u(opacity), stop.offset);
-        }
+        add_stops(&mut gradient, self.stops, opacity, self.color_interpolation);
 
         gradient.apply_transform(options.transform * gradient_transform);
         gradient
     }
-}
\ No newline at end of file
+}
+#[test]
+fn test_gradient_color_interpolation() {
+    let svg = Svg::from_str(r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">
+        <linearGradient id="a" color-interpolation="linearRGB"><stop offset="0" stop-color="red"/><stop offset="1" stop-color="blue"/></linearGradient>
+        <linearGradient id="b" xlink:href="#a"/>
+        <linearGradient id="c"><stop offset="0" stop-color="red"/><stop offset="1" stop-color="blue"/></linearGradient>
+    </svg>"##).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let mut options = Options::new(&ctx);
+    // the painted element doesn't decide how the gradient is interpolated
+    options.color_interpolation = ColorInterpolation::LinearRGB;
+    let stops = |id| match **svg.get_item(id).unwrap() {
+        Item::LinearGradient(ref gradient) => gradient.build(&options, 1.0).stops().len(),
+        _ => unreachable!()
+    };
+    assert_eq!(stops("a"), 9);
+    assert_eq!(stops("b"), 9);
+    assert_eq!(stops("c"), 2);
+}
@@ -18,7 +18,8 @@ mod prelude {
     pub use svg_dom::prelude::*;
     pub use crate::{
         DrawItem, Resolve, Interpolate, Compose, Shape,
-        draw::{Options, DrawContext, BoundsOptions, DrawOptions},
+        effects::Element,
+        draw::{Options, DrawContext, BoundsOptions, DrawOptions, Viewport, Clip},
     };
     pub use svgtypes::{Length, LengthUnit};
 }
@@ -31,6 +32,8 @@ mod attrs;
 mod gradient;
 mod resolve;
 mod filter;
+mod effects;
+mod mask;
 mod g;
 mod draw;
 mod svg;
@@ -40,6 +43,8 @@ mod animate;
 mod paint;
 
 pub use prelude::*;
+#[cfg(feature="text")]
+pub use text::{text_bounds, TextBounds, text_geometry, TextGeometry, CharGeometry};
 
 #[cfg(feature="text")]
 use svg_text::FontCollection;
@@ -66,6 +71,11 @@ pub trait DrawItem {
 pub trait Interpolate: Clone {
     fn lerp(self, to: Self, x: f32) -> Self;
     fn scale(self, x: f32) -> Self;
+
+    /// like `lerp`, but colors are interpolated in the color space selected by `options`
+    fn lerp_with(self, to: Self, x: f32, _options: &Options) -> Self {
+        self.lerp(to, x)
+    }
 }
 impl<T> Interpolate for Option<T> where T: Interpolate {
     fn lerp(self, to: Self, x: f32) -> Self {
@@ -74,6 +84,12 @@ impl<T> Interpolate for Option<T> where T: Interpolate {
             _ => None
         }
     }
+    fn lerp_with(self, to: Self, x: f32, options: &Options) -> Self {
+        match (self, to) {
+            (Some(a), Some(b)) => Some(a.lerp_with(b, x, options)),
+            _ => None
+        }
+    }
     fn scale(self, x: f32) -> Self {
         self.map(|v| v.scale(x))
     }
@@ -87,6 +103,26 @@ impl DrawItem for TagText {
         None
     }
 }
+#[cfg(not(feature="text"))]
+impl Shape for TagText {
+    fn outline(&self, options: &Options) -> Option<Outline> {
+        None
+    }
+}
+#[cfg(not(feature="text"))]
+impl DrawItem for TagTextArea {
+    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
+    }
+    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
+        None
+    }
+}
+#[cfg(not(feature="text"))]
+impl Shape for TagTextArea {
+    fn outline(&self, options: &Options) -> Option<Outline> {
+        None
+    }
+}
 
 pub trait Compose {
     fn compose(self, rhs: Self) -> Self;
@@ -132,6 +168,7 @@ draw_items!(
         Svg(TagSvg),
         Use(TagUse),
         Text(TagText),
+        TextArea(TagTextArea),
     }
 );
 
@@ -55,12 +55,36 @@ macro_rules! wrap_option_iterpolate {
                     (from, to) => $name(from.or(to))
                 }
             }
+            fn lerp_with(self, to: Self, x: f32, options: &Options) -> Self {
+                match (self.0, to.0) {
+                    (Some(from), Some(to)) => $name(Some(from.lerp_with(to, x, options))),
+                    (from, to) => $name(from.or(to))
+                }
+            }
             fn scale(self, x: f32) -> Self {
                 $name(self.0.map(|v| v.scale(x)))
             }
         }
     };
 }
+/// values that can't be interpolated switch halfway, and an animation replaces the base value
+macro_rules! discrete_interpolate {
+    ($name:ident) => {
+        impl Interpolate for $name {
+            fn lerp(self, to: Self, x: f32) -> Self {
+                if x < 0.5 { self } else { to }
+            }
+            fn scale(self, _x: f32) -> Self {
+                self
+            }
+        }
+        impl Compose for $name {
+            fn compose(self, rhs: Self) -> Self {
+                rhs
+            }
+        }
+    };
+}
 macro_rules! get_or_return {
     ($opt:expr) => (
         match $opt {
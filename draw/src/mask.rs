@@ -0,0 +1,64 @@
+use crate::prelude::*;
+use crate::filter::Layer;
+use pathfinder_renderer::{
+    scene::ClipPath,
+    paint::Paint,
+};
+use pathfinder_content::effects::{PatternFilter, BlendMode};
+use pathfinder_color::matrix::ColorMatrix;
+
+/// object bounding box units: lengths are fractions of the bounding box
+fn bbox_options<'a>(options: &Options<'a>, bbox: RectF) -> Options<'a> {
+    let mut options = options.clone();
+    options.apply_transform(Transform2F::from_translation(bbox.origin()) * Transform2F::from_scale(bbox.size()));
+    options.view_box = Some(RectF::new(Vector2F::zero(), Vector2F::splat(1.0)));
+    options
+}
+
+/// masks the content of the current layer, which covers `area`.
+/// `bbox` is the object bounding box of the masked element.
+pub fn draw_mask(mask: &TagMask, scene: &mut Scene, options: &DrawOptions, area: RectF, bbox: Option<RectF>) {
+    let mut mask_options = options.clone();
+    mask_options.opacity = 1.0;
+    let layer = Layer::push(scene, area, &mut mask_options);
+
+    // the mask of an element without a bounding box is empty
+    let region_options = match (mask.mask_units, bbox) {
+        (Units::UserSpaceOnUse, _) => Some(mask_options.common.clone()),
+        (Units::ObjectBoundingBox, Some(bbox)) => Some(bbox_options(&mask_options, bbox)),
+        (Units::ObjectBoundingBox, None) => None,
+    };
+    let content_bbox = match mask.mask_content_units {
+        Units::UserSpaceOnUse => Some(None),
+        Units::ObjectBoundingBox => bbox.map(Some),
+    };
+
+    if let (Some(region_options), Some(content_bbox)) = (region_options, content_bbox) {
+        let percent = |p| Length::new(p, LengthUnit::Percent);
+        let origin = vec2f(
+            mask.x.unwrap_or(LengthX(percent(-10.))).try_resolve(&region_options).unwrap_or(0.),
+            mask.y.unwrap_or(LengthY(percent(-10.))).try_resolve(&region_options).unwrap_or(0.),
+        );
+        let size = vec2f(
+            mask.width.unwrap_or(LengthX(percent(120.))).try_resolve(&region_options).unwrap_or(0.),
+            mask.height.unwrap_or(LengthY(percent(120.))).try_resolve(&region_options).unwrap_or(0.),
+        );
+        let region = Outline::from_rect(RectF::new(origin, size)).transformed(region_options.get_transform());
+        let region_rect = region.bounds();
+        mask_options.clip_path = Some(Clip::new(Some(region_rect), scene.push_clip_path(ClipPath::new(region))));
+
+        if let Some(bbox) = content_bbox {
+            mask_options.common = bbox_options(&mask_options, bbox);
+        }
+        let mask_options = mask_options.apply(scene, &mask.attrs);
+        for item in mask.items.iter() {
+            item.draw_to(scene, &mask_options);
+        }
+    }
+
+    let mut pattern = layer.pattern();
+    if mask.mask_type == MaskType::Luminance {
+        pattern.set_filter(Some(PatternFilter::ColorMatrix(ColorMatrix::luminance_to_alpha())));
+    }
+    layer.composite(scene, Paint::from_pattern(pattern), BlendMode::DestIn, None);
+}
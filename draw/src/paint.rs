@@ -4,12 +4,37 @@ use palette::{
 };
 use pathfinder_color::{ColorF, ColorU};
 
+/// converts the color channels from sRGB to linear light. alpha is unchanged.
+pub fn srgb_to_linear(color: &Color) -> Color {
+    let linear: LinSrgb = Srgb::new(color.red, color.green, color.blue).into_linear();
+    Color::from_srgba(linear.red, linear.green, linear.blue, color.alpha)
+}
+
+/// converts the color channels from linear light to sRGB. alpha is unchanged.
+pub fn linear_to_srgb(color: &Color) -> Color {
+    let srgb = Srgb::from_linear(LinSrgb::new(color.red, color.green, color.blue));
+    Color::from_srgba(srgb.red, srgb.green, srgb.blue, color.alpha)
+}
+
+/// sRGB encoding of a single linear channel value, mirrored for negative values
+pub fn encode_srgb(x: f32) -> f32 {
+    let srgb = Srgb::from_linear(LinSrgb::new(x.abs(), 0.0, 0.0));
+    x.signum() * srgb.red
+}
+
 impl Interpolate for Color {
     fn lerp(self, to: Self, x: f32) -> Self {
         Color {
             red: self.red.lerp(to.red, x),
             green: self.green.lerp(to.green, x),
             blue: self.blue.lerp(to.blue, x),
+            alpha: self.alpha.lerp(to.alpha, x),
+        }
+    }
+    fn lerp_with(self, to: Self, x: f32, options: &Options) -> Self {
+        match options.color_interpolation {
+            ColorInterpolation::LinearRGB => linear_to_srgb(&srgb_to_linear(&self).lerp(srgb_to_linear(&to), x)),
+            ColorInterpolation::SRGB | ColorInterpolation::Auto => self.lerp(to, x)
         }
     }
     fn scale(self, x: f32) -> Self {
@@ -17,6 +42,7 @@ impl Interpolate for Color {
             red: self.red.scale(x),
             green: self.green.scale(x),
             blue: self.blue.scale(x),
+            alpha: self.alpha.scale(x),
         }
     }
 }
@@ -26,10 +52,13 @@ impl Compose for Color {
             red: self.red + rhs.red,
             green: self.green + rhs.green,
             blue: self.blue + rhs.blue,
+            alpha: self.alpha + rhs.alpha,
         }
     }
 }
 
+resolve_clone!(Color);
+
 impl Interpolate for Paint {
     fn lerp(self, to: Self, x: f32) -> Self {
         match (self, to) {
@@ -38,6 +67,12 @@ impl Interpolate for Paint {
             (a, _) => a
         }
     }
+    fn lerp_with(self, to: Self, x: f32, options: &Options) -> Self {
+        match (self, to) {
+            (Paint::Color(a), Paint::Color(b)) => Paint::Color(a.lerp_with(b, x, options)),
+            (a, b) => a.lerp(b, x)
+        }
+    }
     fn scale(self, x: f32) -> Self {
         match self {
             Paint::Color(a) => Paint::Color(a.scale(x)),
@@ -1,45 +1,275 @@
-use pathfinder_content::outline::Outline;
+use pathfinder_content::{
+    outline::{Outline, Contour, ContourIterFlags, PushSegmentFlags},
+    segment::Segment,
+    fill::FillRule,
+};
 use crate::prelude::*;
 
-impl Resolve for TagClipPath {
-    type Output = Outline;
-    fn resolve(&self, options: &Options) -> Outline {
-        let mut outline = Outline::new();
-        for item in &self.items {
-            let o = match item {
-                Item::Path(path) => path.outline(options),
-                Item::Rect(rect) => rect.outline(options),
-                Item::Circle(circle) => circle.outline(options),
-                Item::Polygon(polygon) => polygon.outline(options),
-                _ => None
-            };
-            if let Some(o) = o {
-                outline.push_outline(o)
+/// outline of the geometry of an item, transformed by `options`
+pub fn item_outline(item: &Item, options: &Options) -> Option<Outline> {
+    match *item {
+        Item::Path(ref tag) => tag.outline(options),
+        Item::Rect(ref tag) => tag.outline(options),
+        Item::Circle(ref tag) => tag.outline(options),
+        Item::Ellipse(ref tag) => tag.outline(options),
+        Item::Polygon(ref tag) => tag.outline(options),
+        Item::Polyline(ref tag) => tag.outline(options),
+        Item::Line(ref tag) => tag.outline(options),
+        Item::Text(ref tag) => tag.outline(options),
+        Item::TextArea(ref tag) => tag.outline(options),
+        Item::Use(ref tag) => tag.outline(options),
+        Item::G(ref tag) => tag.outline(options),
+        _ => None
+    }
+}
+
+/// bounding box of the geometry of the items in the user space of `options`, ignoring the stroke
+pub fn object_bounds<'i>(items: impl IntoIterator<Item=&'i Item>, options: &Options) -> Option<RectF> {
+    let mut options = options.clone();
+    options.set_transform(Transform2F::default());
+    max_bounds(items.into_iter()
+        .flat_map(|item| item_outline(item, &options))
+        .filter(|o| o.len() > 0)
+        .map(|o| o.bounds())
+    )
+}
+
+/// outline of the clip path, to be filled with the nonzero rule.
+/// `bbox` is the object bounding box of the clipped element, needed for `clipPathUnits="objectBoundingBox"`.
+pub fn clip_outline(clip: &TagClipPath, options: &Options, bbox: Option<RectF>) -> Outline {
+    let mut options = options.apply(&clip.attrs);
+    let mut outline = Outline::new();
+    if clip.clip_path_units == Units::ObjectBoundingBox {
+        match bbox {
+            Some(bbox) => options.apply_transform(Transform2F::from_translation(bbox.origin()) * Transform2F::from_scale(bbox.size())),
+            // without a bounding box nothing is visible
+            None => return outline
+        }
+    }
+
+    for item in &clip.items {
+        let attrs = match shape_attrs(item) {
+            Some(attrs) => attrs,
+            None => continue
+        };
+        let mut child = options.apply(attrs);
+        // a `<use>` clips with the rule of the element it references, which inherits from the `<use>`
+        if let Item::Use(TagUse { href: Some(ref href), .. }) = *item {
+            if let Some(attrs) = options.ctx.resolve_href(href).and_then(|item| shape_attrs(item)) {
+                child = child.apply(attrs);
+            }
+        }
+        if let Some(o) = item_outline(item, &options) {
+            match child.clip_rule {
+                FillRule::EvenOdd => outline.push_outline(evenodd_to_nonzero(&o)),
+                FillRule::Winding => outline.push_outline(o),
+            }
+        }
+    }
+    outline
+}
+
+/// attributes of the elements that can be children of a clip path
+fn shape_attrs(item: &Item) -> Option<&Attrs> {
+    match *item {
+        Item::Path(TagPath { ref attrs, .. }) |
+        Item::Rect(TagRect { ref attrs, .. }) |
+        Item::Polygon(TagPolygon { ref attrs, .. }) |
+        Item::Polyline(TagPolyline { ref attrs, .. }) |
+        Item::Line(TagLine { ref attrs, .. }) |
+        Item::Ellipse(TagEllipse { ref attrs, .. }) |
+        Item::Circle(TagCircle { ref attrs, .. }) |
+        Item::Text(TagText { ref attrs, .. }) |
+        Item::TextArea(TagTextArea { ref attrs, .. }) |
+        Item::Use(TagUse { ref attrs, .. }) => Some(attrs),
+        _ => None
+    }
+}
+
+/// the outline with its contours turned such that the nonzero rule fills what the evenodd rule does:
+/// contours inside an even number of other contours run counterclockwise, the others clockwise.
+/// this is exact as long as the contours don't intersect each other or themselves.
+fn evenodd_to_nonzero(outline: &Outline) -> Outline {
+    // the control points approximate the curves closely enough to tell inside from outside
+    let polygons: Vec<Vec<Vector2F>> = outline.contours().iter()
+        .map(|contour| (0 .. contour.len()).map(|i| contour.position_of(i)).collect())
+        .collect();
+
+    let mut result = Outline::new();
+    for (i, contour) in outline.contours().iter().enumerate() {
+        let depth = match polygons[i].first() {
+            Some(&point) => polygons.iter().enumerate()
+                .filter(|&(j, polygon)| j != i && polygon_contains(polygon, point))
+                .count(),
+            None => 0
+        };
+        let counterclockwise = signed_area(&polygons[i]) > 0.0;
+        if counterclockwise == (depth % 2 == 0) {
+            result.push_contour(contour.clone());
+        } else {
+            result.push_contour(reversed_contour(contour));
+        }
+    }
+    result
+}
+
+fn signed_area(polygon: &[Vector2F]) -> f32 {
+    let n = polygon.len();
+    0.5 * (0 .. n).map(|i| polygon[i].det(polygon[(i + 1) % n])).sum::<f32>()
+}
+
+/// whether `point` is inside the polygon by the evenodd rule
+fn polygon_contains(polygon: &[Vector2F], point: Vector2F) -> bool {
+    let n = polygon.len();
+    let mut inside = false;
+    for i in 0 .. n {
+        let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+        if (a.y() > point.y()) != (b.y() > point.y()) {
+            let x = a.x() + (point.y() - a.y()) / (b.y() - a.y()) * (b.x() - a.x());
+            if point.x() < x {
+                inside = !inside;
             }
         }
-        outline
     }
+    inside
+}
+
+fn reversed_contour(contour: &Contour) -> Contour {
+    let segments: Vec<Segment> = contour.iter(ContourIterFlags::empty()).collect();
+    let mut reversed = Contour::new();
+    for (i, segment) in segments.iter().rev().enumerate() {
+        let flags = match i {
+            0 => PushSegmentFlags::UPDATE_BOUNDS | PushSegmentFlags::INCLUDE_FROM_POINT,
+            _ => PushSegmentFlags::UPDATE_BOUNDS,
+        };
+        reversed.push_segment(&segment.reversed(), flags);
+    }
+    reversed.close();
+    reversed
 }
 
 impl Shape for TagPath {
     fn outline(&self, options: &Options) -> Option<Outline> {
+        if !self.attrs.display.resolve(options) {
+            return None;
+        }
         let options = options.apply(&self.attrs);
         Some(self.outline.clone().transformed(options.get_transform()))
     }
 }
 
 
-impl DrawItem for TagPath {
-    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
-        if self.attrs.display && self.outline.len() > 0 {
-            let options = options.apply(&self.attrs);
+impl Element for TagPath {
+    fn attrs(&self) -> &Attrs {
+        &self.attrs
+    }
+    fn bbox(&self, _options: &Options) -> Option<RectF> {
+        Some(self.outline.bounds())
+    }
+    fn content_bounds(&self, options: &BoundsOptions) -> Option<RectF> {
+        if self.outline.len() > 0 {
             options.bounds(self.outline.bounds())
         } else {
             None
         }
     }
-    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        let options = options.apply(scene, &self.attrs);
+    fn overlaps(&self, options: &Options) -> bool {
+        options.has_fill() && options.has_stroke()
+    }
+    fn draw_content(&self, scene: &mut Scene, options: &DrawOptions) {
         options.draw(scene, &self.outline);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+fn test_clip(svg: &str, id: &str, bbox: Option<RectF>) -> Outline {
+    let svg = Svg::from_str(svg).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let mut options = Options::new(&ctx);
+    options.set_transform(Transform2F::default());
+    match **svg.get_item(id).unwrap() {
+        Item::ClipPath(ref clip) => clip_outline(clip, &options, bbox),
+        ref item => panic!("not a clip path: {:?}", item)
+    }
+}
+
+#[test]
+fn test_clip_transform() {
+    let outline = test_clip(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <clipPath id="c" transform="translate(5 0)"><rect x="0" y="0" width="10" height="10" transform="scale(2)"/></clipPath>
+    </svg>"#, "c", None);
+    assert_eq!(outline.bounds(), RectF::new(vec2f(5., 0.), vec2f(20., 20.)));
+}
+
+#[test]
+fn test_clip_object_bounding_box() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <clipPath id="c" clipPathUnits="objectBoundingBox"><rect x="0.25" y="0" width="0.5" height="1"/></clipPath>
+    </svg>"#;
+    let outline = test_clip(svg, "c", Some(RectF::new(vec2f(10., 10.), vec2f(100., 50.))));
+    assert_eq!(outline.bounds(), RectF::new(vec2f(35., 10.), vec2f(50., 50.)));
+    // nothing is visible without a bounding box
+    assert_eq!(test_clip(svg, "c", None).len(), 0);
+}
+
+#[test]
+fn test_clip_children() {
+    let outline = test_clip(r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">
+        <defs><circle id="dot" cx="50" cy="50" r="10"/></defs>
+        <clipPath id="c">
+            <rect width="10" height="10" clip-rule="evenodd"/>
+            <use xlink:href="#dot"/>
+            <line x2="100" y2="100"/>
+            <g><rect width="1000" height="1000"/></g>
+        </clipPath>
+    </svg>"#, "c", None);
+    // groups are not allowed as children
+    assert_eq!(outline.bounds(), RectF::new(vec2f(0., 0.), vec2f(100., 100.)));
+}
+
+#[test]
+fn test_clip_rules() {
+    let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">
+        <defs><path id="ring" d="M0 0H30V30H0Z M10 10H20V20H10Z" clip-rule="evenodd"/></defs>
+        <clipPath id="c">
+            <path d="M0 0H30V30H0Z M10 10H20V20H10Z" clip-rule="evenodd"/>
+            <path d="M40 0H70V30H40Z M50 10H60V20H50Z"/>
+            <use xlink:href="#ring" x="80"/>
+        </clipPath>
+    </svg>"##;
+    let outline = test_clip(svg, "c", None);
+    let areas: Vec<f32> = outline.contours().iter()
+        .map(|contour| signed_area(&(0 .. contour.len()).map(|i| contour.position_of(i)).collect::<Vec<_>>()))
+        .collect();
+    // the holes of the evenodd children wind the other way, so they stay empty in a single nonzero outline.
+    // the `<use>` takes the clip rule of the referenced path.
+    assert_eq!(areas.len(), 6);
+    assert!(areas[0] * areas[1] < 0.);
+    assert!(areas[2] * areas[3] > 0.);
+    assert!(areas[4] * areas[5] < 0.);
+}
+
+#[test]
+fn test_nested_clip_path() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <clipPath id="a" clip-path="url(#b)"><rect width="20" height="20"/></clipPath>
+        <clipPath id="b"><rect x="10" y="5" width="20" height="20"/></clipPath>
+        <clipPath id="c" clip-path="url(#b)"><rect x="50" width="20" height="20"/></clipPath>
+        <rect id="r" width="100" height="100" clip-path="url(#a)"/>
+        <rect id="s" width="100" height="100" clip-path="url(#c)"/>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let mut options = BoundsOptions::new(&ctx);
+    options.set_transform(Transform2F::default());
+    let attrs = |id| match **svg.get_item(id).unwrap() {
+        Item::Rect(ref rect) => &rect.attrs,
+        _ => unreachable!()
+    };
+    let clipped = options.apply(attrs("r"));
+    assert_eq!(clipped.clip_rect, Some(Some(RectF::new(vec2f(10., 5.), vec2f(10., 15.)))));
+
+    // the clip paths don't overlap, so nothing is visible
+    let clipped = options.apply(attrs("s"));
+    assert_eq!(clipped.clip_rect, Some(None));
+    assert_eq!(clipped.bounds(RectF::new(vec2f(0., 0.), vec2f(100., 100.))), None);
+}
@@ -5,64 +5,105 @@ use svgtypes::PointsParser;
 
 impl Shape for TagPolygon {
     fn outline(&self, options: &Options) -> Option<Outline> {
+        if !self.attrs.display.resolve(options) {
+            return None;
+        }
         let options = options.apply(&self.attrs);
         Some(self.outline.clone().transformed(options.get_transform()))
     }
 }
-impl DrawItem for TagPolygon {
-    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
-        if self.attrs.display && self.outline.len() > 0 {
-            let options = options.apply(&self.attrs);
+impl Element for TagPolygon {
+    fn attrs(&self) -> &Attrs {
+        &self.attrs
+    }
+    fn bbox(&self, _options: &Options) -> Option<RectF> {
+        Some(self.outline.bounds())
+    }
+    fn content_bounds(&self, options: &BoundsOptions) -> Option<RectF> {
+        if self.outline.len() > 0 {
             options.bounds(self.outline.bounds())
         } else {
             None
         }
     }
-    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        let options = options.apply(scene, &self.attrs);
+    fn overlaps(&self, options: &Options) -> bool {
+        options.has_fill() && options.has_stroke()
+    }
+    fn draw_content(&self, scene: &mut Scene, options: &DrawOptions) {
         options.draw(scene, &self.outline);
     }
 }
 
-impl DrawItem for TagPolyline {
-    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
-        if self.attrs.display && self.outline.len() > 0 {
-            let options = options.apply(&self.attrs);
+impl Shape for TagPolyline {
+    fn outline(&self, options: &Options) -> Option<Outline> {
+        if !self.attrs.display.resolve(options) {
+            return None;
+        }
+        let options = options.apply(&self.attrs);
+        Some(self.outline.clone().transformed(options.get_transform()))
+    }
+}
+impl Element for TagPolyline {
+    fn attrs(&self) -> &Attrs {
+        &self.attrs
+    }
+    fn bbox(&self, _options: &Options) -> Option<RectF> {
+        Some(self.outline.bounds())
+    }
+    fn content_bounds(&self, options: &BoundsOptions) -> Option<RectF> {
+        if self.outline.len() > 0 {
             options.bounds(self.outline.bounds())
         } else {
             None
         }
     }
-    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        let options = options.apply(scene, &self.attrs);
+    fn overlaps(&self, options: &Options) -> bool {
+        options.has_fill() && options.has_stroke()
+    }
+    fn draw_content(&self, scene: &mut Scene, options: &DrawOptions) {
         options.draw(scene, &self.outline);
     }
 }
 
-impl DrawItem for TagLine {
-    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
-        if self.attrs.display {
-            let options = options.apply(&self.attrs);
-            let p1 = self.p1.resolve(&options);
-            let p2 = self.p2.resolve(&options);
-            Some(RectF::from_points(p1.min(p2), p1.max(p2)))
-        } else {
-            None
+fn line_outline(tag: &TagLine, options: &Options) -> Outline {
+    let p1 = tag.p1.resolve(options);
+    let p2 = tag.p2.resolve(options);
+
+    let mut contour = Contour::with_capacity(2);
+    contour.push_endpoint(p1);
+    contour.push_endpoint(p2);
+
+    let mut outline = Outline::with_capacity(1);
+    outline.push_contour(contour);
+    outline
+}
+
+impl Shape for TagLine {
+    fn outline(&self, options: &Options) -> Option<Outline> {
+        if !self.attrs.display.resolve(options) {
+            return None;
         }
+        let options = options.apply(&self.attrs);
+        Some(line_outline(self, &options).transformed(options.get_transform()))
+    }
+}
+impl Element for TagLine {
+    fn attrs(&self) -> &Attrs {
+        &self.attrs
     }
-    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        let options = options.apply(scene, &self.attrs);
+    fn bbox(&self, options: &Options) -> Option<RectF> {
+        Some(line_outline(self, options).bounds())
+    }
+    fn content_bounds(&self, options: &BoundsOptions) -> Option<RectF> {
         let p1 = self.p1.resolve(&options);
         let p2 = self.p2.resolve(&options);
-
-        let mut contour = Contour::with_capacity(2);
-        contour.push_endpoint(p1);
-        contour.push_endpoint(p2);
-
-        let mut outline = Outline::with_capacity(1);
-        outline.push_contour(contour);
-
+        Some(RectF::from_points(p1.min(p2), p1.max(p2)))
+    }
+    fn overlaps(&self, _options: &Options) -> bool {
+        false
+    }
+    fn draw_content(&self, scene: &mut Scene, options: &DrawOptions) {
+        let outline = line_outline(self, &options);
         options.draw(scene, &outline);
     }
 }
-
@@ -2,7 +2,7 @@ use crate::prelude::*;
 use pathfinder_content::outline::{Outline, Contour};
 
 fn rect_outline<'a>(tag: &TagRect, options: &Options<'a>) -> Option<Outline> {
-    if !tag.attrs.display {
+    if !tag.attrs.display.resolve(options) {
         return None;
     }
 
@@ -33,19 +33,14 @@ impl Shape for TagRect {
         rect_outline(self, &options).map(|o| o.transformed(options.get_transform()))
     }
 }
-impl DrawItem for TagRect {
-    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        let options = options.apply(scene, &self.attrs);
-        if let Some(outline) = rect_outline(self, &options) {
-            options.draw(scene, &outline);
-        }
+impl Element for TagRect {
+    fn attrs(&self) -> &Attrs {
+        &self.attrs
     }
-    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
-        if !self.attrs.display {
-            return None;
-        }
-        let options = options.apply(&self.attrs);
-
+    fn bbox(&self, options: &Options) -> Option<RectF> {
+        rect_outline(self, options).map(|o| o.bounds())
+    }
+    fn content_bounds(&self, options: &BoundsOptions) -> Option<RectF> {
         let size = self.size.resolve(&options);
         if (size.x() == 0.) || (size.y() == 0.) {
             return None;
@@ -55,4 +50,12 @@ impl DrawItem for TagRect {
 
         options.bounds(RectF::new(origin, size))
     }
+    fn overlaps(&self, options: &Options) -> bool {
+        options.has_fill() && options.has_stroke()
+    }
+    fn draw_content(&self, scene: &mut Scene, options: &DrawOptions) {
+        if let Some(outline) = rect_outline(self, &options) {
+            options.draw(scene, &outline);
+        }
+    }
 }
@@ -16,21 +16,35 @@ impl<T> Resolve for Value<T> where T: Resolve + Parse + Clone, T::Output: Interp
         let base = self.value.resolve(options);
         self.animations.iter().fold(base, |base, animation| apply_anim(animation, base, options))
     }
+    fn try_resolve(&self, options: &Options) -> Option<T::Output> {
+        let base = self.value.try_resolve(options)?;
+        Some(self.animations.iter().fold(base, |base, animation| apply_anim(animation, base, options)))
+    }
 }
 impl<T> Resolve for Option<T> where T: Resolve {
     type Output = Option<T::Output>;
     fn resolve(&self, options: &Options) -> Option<T::Output> {
         self.as_ref().map(|val| val.resolve(options))
     }
+    /// `Some(None)` without a value, `None` if the value can't be resolved
     fn try_resolve(&self, options: &Options) -> Option<Self::Output> {
-        self.as_ref().map(|val| val.try_resolve(options))
+        match *self {
+            Some(ref val) => val.try_resolve(options).map(Some),
+            None => Some(None)
+        }
     }
 }
 
+/// the initial value of lengths that can't be resolved
+fn unresolved(length: &impl std::fmt::Debug) -> f32 {
+    warn!("can't resolve {:?}, using 0", length);
+    0.0
+}
+
 impl Resolve for Length {
     type Output = f32;
     fn resolve(&self, options: &Options) -> Self::Output {
-        options.resolve_length(*self).unwrap()
+        options.resolve_length(*self).unwrap_or_else(|| unresolved(self))
     }
     fn try_resolve(&self, options: &Options) -> Option<Self::Output> {
         options.resolve_length(*self)
@@ -40,7 +54,7 @@ impl Resolve for Length {
 impl Resolve for LengthX {
     type Output = f32;
     fn resolve(&self, options: &Options) -> Self::Output {
-        options.resolve_length_along(self.0, Axis::X).unwrap()
+        options.resolve_length_along(self.0, Axis::X).unwrap_or_else(|| unresolved(self))
     }
     fn try_resolve(&self, options: &Options) -> Option<Self::Output> {
         options.resolve_length_along(self.0, Axis::X)
@@ -49,12 +63,25 @@ impl Resolve for LengthX {
 impl Resolve for LengthY {
     type Output = f32;
     fn resolve(&self, options: &Options) -> Self::Output {
-        options.resolve_length_along(self.0, Axis::Y).unwrap()
+        options.resolve_length_along(self.0, Axis::Y).unwrap_or_else(|| unresolved(self))
     }
     fn try_resolve(&self, options: &Options) -> Option<Self::Output> {
         options.resolve_length_along(self.0, Axis::Y)
     }
 }
+impl Resolve for FontSize {
+    type Output = f32;
+    fn resolve(&self, options: &Options) -> Self::Output {
+        self.try_resolve(options).unwrap_or_else(|| unresolved(self))
+    }
+    /// `options` are those of the parent element
+    fn try_resolve(&self, options: &Options) -> Option<Self::Output> {
+        match self.0.unit {
+            LengthUnit::Percent => Some(options.font_size * self.0.num as f32 / 100.0),
+            _ => options.resolve_length(self.0),
+        }
+    }
+}
 impl Resolve for Vector {
     type Output = Vector2F;
     fn resolve(&self, options: &Options) -> Self::Output {
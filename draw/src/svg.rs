@@ -1,17 +1,81 @@
 use crate::prelude::*;
+use crate::path::object_bounds;
+use crate::g::needs_layer;
 
-impl DrawItem for TagSvg {
-    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
-        self.view_box.as_ref().map(|r| r.resolve(options))
-        .or_else(|| max_bounds(self.items.iter().flat_map(|item| item.bounds(&options))))
+fn percent(p: f64) -> Length {
+    Length::new(p, LengthUnit::Percent)
+}
+
+/// the viewport of `svg` in the user space of `options`
+pub fn svg_viewport<'i>(svg: &'i TagSvg, options: &Options) -> Viewport<'i> {
+    // x and y have no effect on the outermost svg, and it isn't clipped either
+    let is_root = match *options.ctx.svg.root {
+        Item::Svg(ref root) => std::ptr::eq(root, svg),
+        _ => false
+    };
+    let origin = match is_root {
+        true => Vector2F::zero(),
+        false => vec2f(
+            svg.x.map(|x| x.resolve(options)).unwrap_or(0.0),
+            svg.y.map(|y| y.resolve(options)).unwrap_or(0.0),
+        )
+    };
+    let view_box = svg.view_box.as_ref().and_then(|r| r.size().try_resolve(options));
+    let width = svg.width.unwrap_or(LengthX(percent(100.))).try_resolve(options)
+        .or(view_box.map(|s| s.x()));
+    let height = svg.height.unwrap_or(LengthY(percent(100.))).try_resolve(options)
+        .or(view_box.map(|s| s.y()));
+    Viewport {
+        rect: RectF::new(origin, vec2f(width.unwrap_or(0.0), height.unwrap_or(0.0))),
+        has_size: width.is_some() && height.is_some(),
+        view_box: svg.view_box.as_ref(),
+        aspect: svg.preserve_aspect_ratio,
+        clip: !is_root && svg.attrs.overflow != Some(Overflow::Visible),
     }
-    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        let mut options = options.apply(scene, &self.attrs);
-        if let Some(ref view_box) = self.view_box {
-            options.apply_viewbox(self.width, self.height, view_box);
-        }
+}
+
+impl Element for TagSvg {
+    fn attrs(&self) -> &Attrs {
+        &self.attrs
+    }
+    fn bbox(&self, options: &Options) -> Option<RectF> {
+        let mut options = options.clone();
+        options.set_transform(Transform2F::default());
+        options.apply_viewport(&svg_viewport(self, &options));
+        object_bounds(self.items.iter().map(|i| &**i), &options)
+            .map(|r| *options.get_transform() * r)
+    }
+    fn content_bounds(&self, options: &BoundsOptions) -> Option<RectF> {
+        let mut options = options.clone();
+        options.enter_viewport(&svg_viewport(self, &options));
+        max_bounds(self.items.iter().flat_map(|item| item.bounds(&options)))
+    }
+    fn overlaps(&self, options: &Options) -> bool {
+        needs_layer(&self.items, options)
+    }
+    fn draw_content(&self, scene: &mut Scene, options: &DrawOptions) {
+        let mut options = options.clone();
+        options.enter_viewport(scene, &svg_viewport(self, &options));
         for item in self.items.iter() {
             item.draw_to(scene, &options);
         }
     }
 }
+
+#[test]
+fn test_root_viewport() {
+    let view_box = |text: &str| {
+        let svg = Svg::from_str(text).unwrap();
+        let ctx = DrawContext::new_without_fonts(&svg);
+        let mut options = Options::new(&ctx);
+        match *svg.root {
+            Item::Svg(ref root) => options.apply_viewport(&svg_viewport(root, &options)),
+            _ => unreachable!()
+        }
+        options.view_box
+    };
+    assert_eq!(view_box(r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100"/>"#), Some(RectF::new(Vector2F::zero(), vec2f(200., 100.))));
+    assert_eq!(view_box(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="10 10 50 40"/>"#), Some(RectF::new(vec2f(10., 10.), vec2f(50., 40.))));
+    // without a size percentages can't be resolved, instead of resolving to 0
+    assert_eq!(view_box(r#"<svg xmlns="http://www.w3.org/2000/svg"/>"#), None);
+}
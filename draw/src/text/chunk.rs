@@ -1,5 +1,5 @@
 use unic_bidi::{Level, LevelRun, BidiInfo};
-use svg_text::{FontCollection, Layout};
+use svg_text::{FontCollection, Layout, LayoutOptions};
 use svg_dom::TextFlow;
 use pathfinder_geometry::vector::Vector2F;
 use isolang::Language;
@@ -29,14 +29,15 @@ impl Chunk {
             runs
         }
     }
-    pub fn layout(&self, font: &FontCollection, lang: Option<Language>) -> ChunkLayout {
+    pub fn layout(&self, font: &FontCollection, lang: Option<Language>, options: &LayoutOptions) -> ChunkLayout {
         let mut offset = Vector2F::zero();
         let mut parts = Vec::with_capacity(self.runs.len());
         for (level, run) in self.runs.iter() {
-            let layout = font.layout_run(&self.text[run.clone()], level.is_rtl(), lang);
+            let layout = font.layout_run(&self.text[run.clone()], level.is_rtl(), lang, options);
 
             let advance = layout.metrics.advance;
-            let (run_offset, next_offset) = match level.is_rtl() {
+            // vertical runs all go down, in visual order
+            let (run_offset, next_offset) = match level.is_rtl() && !options.vertical {
                 false => (offset, offset + advance),
                 true => (offset - advance, offset - advance),
             };
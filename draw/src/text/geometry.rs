@@ -0,0 +1,200 @@
+//! positions of the characters of a text, for the methods of the SVG DOM `SVGTextContentElement`
+
+use super::*;
+
+/// the geometry of one typographic character in the user space of the text
+#[derive(Debug, Copy, Clone)]
+pub struct CharGeometry {
+    /// where the glyph starts on the baseline
+    pub start: Vector2F,
+    /// where the next glyph would start without spacing adjustments from `x`, `y`, `dx` or `dy`
+    pub end: Vector2F,
+    /// bounding box of the character cell
+    pub extent: RectF,
+    /// the rotation from `rotate` in degrees
+    pub rotation: f32,
+}
+
+/// the characters of a text element, counted in unicode scalar values.
+/// characters that are rendered together with the previous ones, like the parts of a ligature
+/// or the marks of a grapheme cluster, share its geometry.
+#[derive(Debug)]
+pub struct TextGeometry {
+    /// index into `clusters` for each character, `None` if it was not rendered.
+    /// characters after the last rendered one are missing.
+    chars: Vec<Option<usize>>,
+    clusters: Vec<CharGeometry>,
+}
+impl TextGeometry {
+    /// `getNumberOfChars`
+    pub fn number_of_chars(&self) -> usize {
+        self.chars.len()
+    }
+    /// `getComputedTextLength`, the sum of the advances of all characters
+    pub fn computed_text_length(&self) -> f32 {
+        self.clusters.iter().map(|c| (c.end - c.start).length()).sum()
+    }
+    pub fn char_geometry(&self, idx: usize) -> Option<&CharGeometry> {
+        self.chars.get(idx).cloned().flatten().map(|c| &self.clusters[c])
+    }
+    /// `getStartPositionOfChar`
+    pub fn start_position_of_char(&self, idx: usize) -> Option<Vector2F> {
+        self.char_geometry(idx).map(|c| c.start)
+    }
+    /// `getEndPositionOfChar`
+    pub fn end_position_of_char(&self, idx: usize) -> Option<Vector2F> {
+        self.char_geometry(idx).map(|c| c.end)
+    }
+    /// `getExtentOfChar`
+    pub fn extent_of_char(&self, idx: usize) -> Option<RectF> {
+        self.char_geometry(idx).map(|c| c.extent)
+    }
+    /// `getRotationOfChar`
+    pub fn rotation_of_char(&self, idx: usize) -> Option<f32> {
+        self.char_geometry(idx).map(|c| c.rotation)
+    }
+    /// `getCharNumAtPosition`, the last rendered character whose cell contains `point`
+    pub fn char_num_at_position(&self, point: Vector2F) -> Option<usize> {
+        let cluster = self.clusters.iter().rposition(|c| c.extent.contains_point(point))?;
+        self.chars.iter().position(|&c| c == Some(cluster))
+    }
+}
+
+/// the geometry of the characters of `tag`. `options` already has the attributes of the text applied.
+pub fn text_geometry(tag: &TagText, options: &Options) -> Option<TextGeometry> {
+    let mut options = options.clone();
+    options.set_transform(Transform2F::default());
+    let options = BoundsOptions { common: options, clip_rect: None };
+    let font_cache = options.ctx.font_cache.as_ref()?;
+    let mut sink = GeometrySink { geometry: TextGeometry { chars: vec![], clusters: vec![] }, text: String::new(), first_char: 0 };
+    tag.layout(&mut sink, &options, font_cache);
+    Some(sink.geometry)
+}
+
+/// collects the cells of the characters
+struct GeometrySink {
+    geometry: TextGeometry,
+    text: String,
+    first_char: usize,
+}
+impl<'a> TextSink<BoundsOptions<'a>> for GeometrySink {
+    fn apply(&mut self, options: &BoundsOptions<'a>, attrs: &Attrs) -> BoundsOptions<'a> {
+        options.apply(attrs)
+    }
+    fn text(&mut self, text: &str, first_char: usize) {
+        self.text = text.into();
+        self.first_char = first_char;
+    }
+    fn chunk(&mut self, options: &BoundsOptions<'a>, font_collection: &FontCollection, layout: &ChunkLayout, state: TextState) {
+        let tr = text_transform(options, state);
+        let baseline = baseline_offset(font_collection, options);
+
+        // index of the first character of the grapheme cluster of each byte of the text,
+        // so the glyphs of a base character and its marks make up one cluster
+        let mut char_of_byte = vec![0; self.text.len() + 1];
+        let mut num_chars = 0;
+        for (idx, grapheme) in GraphemeIndices::new(&self.text) {
+            for b in idx .. idx + grapheme.len() {
+                char_of_byte[b] = num_chars;
+            }
+            num_chars += grapheme.chars().count();
+        }
+        let chars = &mut self.geometry.chars;
+        if chars.len() < self.first_char + num_chars {
+            chars.resize(self.first_char + num_chars, None);
+        }
+
+        let first = self.geometry.clusters.len();
+        for &(run_start, run_offset, ref sublayout) in &layout.parts {
+            let mut pen = run_offset;
+            for glyph in &sublayout.glyphs {
+                let (start, end) = (pen, pen + glyph.advance);
+                pen = end;
+                let idx = self.first_char + char_of_byte[run_start + glyph.index];
+                let extent = tr * (cell_rect(font_collection, options, inline(options, start), inline(options, end)) + baseline);
+                let geometry = CharGeometry {
+                    start: tr * (start + baseline),
+                    end: tr * (end + baseline),
+                    extent,
+                    rotation: state.rot,
+                };
+                match chars[idx] {
+                    // more glyphs for the same character, like a base and its marks
+                    Some(c) if c >= first => {
+                        let cluster = &mut self.geometry.clusters[c];
+                        cluster.end = geometry.end;
+                        cluster.extent = cluster.extent.union_rect(extent);
+                    }
+                    _ => {
+                        chars[idx] = Some(self.geometry.clusters.len());
+                        self.geometry.clusters.push(geometry);
+                    }
+                }
+            }
+        }
+
+        // characters without a glyph belong to the previous one
+        for idx in self.first_char + 1 .. self.first_char + num_chars {
+            if chars[idx].is_none() {
+                chars[idx] = chars[idx - 1];
+            }
+        }
+    }
+    fn decoration(&mut self, _options: &BoundsOptions<'a>, _rect: RectF) {}
+}
+
+#[cfg(test)]
+fn close(a: Option<Vector2F>, b: Option<Vector2F>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => (a - b).length() < 1e-3,
+        _ => false
+    }
+}
+
+#[test]
+fn test_char_geometry() {
+    let geometry = test_geometry(r#"<text id="t" font-size="10" rotate="0 0 30">ab<tspan dy="5">cd</tspan></text>"#);
+    assert_eq!(geometry.number_of_chars(), 4);
+    assert!(close(geometry.start_position_of_char(0), Some(Vector2F::zero())));
+    assert!(close(geometry.start_position_of_char(1), geometry.end_position_of_char(0)));
+    assert!((geometry.start_position_of_char(2).unwrap().y() - 5.).abs() < 1e-3);
+    assert_eq!(geometry.start_position_of_char(4), None);
+    assert_eq!(geometry.rotation_of_char(1), Some(0.));
+    assert_eq!(geometry.rotation_of_char(2), Some(30.));
+    // the last rotation applies to the remaining characters
+    assert_eq!(geometry.rotation_of_char(3), Some(30.));
+
+    let advances: f32 = (0 .. 4).map(|idx| {
+        (geometry.end_position_of_char(idx).unwrap() - geometry.start_position_of_char(idx).unwrap()).length()
+    }).sum();
+    assert!((geometry.computed_text_length() - advances).abs() < 1e-3);
+
+    let center = geometry.extent_of_char(0).unwrap().center();
+    assert_eq!(geometry.char_num_at_position(center), Some(0));
+    assert_eq!(geometry.char_num_at_position(vec2f(-100., 0.)), None);
+}
+
+#[test]
+fn test_char_clusters() {
+    // the mark belongs to the cluster of the "e", the "x" follows it
+    let geometry = test_geometry("<text id=\"t\" font-size=\"10\">e\u{301}x</text>");
+    assert_eq!(geometry.number_of_chars(), 3);
+    assert!(close(geometry.start_position_of_char(1), geometry.start_position_of_char(0)));
+    assert!(close(geometry.end_position_of_char(1), geometry.end_position_of_char(0)));
+    assert!(close(geometry.start_position_of_char(2), geometry.end_position_of_char(0)));
+    let center = geometry.extent_of_char(1).unwrap().center();
+    assert_eq!(geometry.char_num_at_position(center), Some(0));
+
+    // the characters of a ligature share its glyph
+    let font = r#"<defs><font horiz-adv-x="1000"><font-face font-family="Lig" units-per-em="1000"/>
+        <glyph unicode="f" d="M0 0h500v500h-500z"/>
+        <glyph unicode="fi" horiz-adv-x="800" d="M0 0h800v500h-800z"/>
+        <glyph unicode="i" d="M0 0h500v500h-500z"/>
+    </font></defs>"#;
+    let geometry = test_geometry(&format!(r#"{}<text id="t" font-family="Lig" font-size="10">fif</text>"#, font));
+    assert_eq!(geometry.number_of_chars(), 3);
+    assert!(close(geometry.start_position_of_char(1), geometry.start_position_of_char(0)));
+    let x = |p: Option<Vector2F>| p.unwrap().x();
+    assert!((x(geometry.end_position_of_char(1)) - 8.).abs() < 1e-3);
+    assert!((x(geometry.start_position_of_char(2)) - 8.).abs() < 1e-3);
+}
@@ -1,19 +1,39 @@
 mod chunk;
+mod wrap;
+mod geometry;
+
+pub use geometry::{text_geometry, TextGeometry, CharGeometry};
 
 use crate::prelude::*;
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+use std::rc::Rc;
+use std::cell::RefCell;
 use std::fmt;
-use svg_text::{Font, FontCollection};
+use std::ops::Deref;
+use svg_text::{Font, FontCollection, FontDatabase, FaceDescription, FamilyName, Generic, Slant, LayoutGlyph, LayoutOptions, Orientation, DecorationLine,
+    SvgFontData, SvgGlyphData, SvgKernPair};
 use chunk::{Chunk, ChunkLayout};
 use crate::draw_glyph;
 use unic_segment::{WordBounds, GraphemeIndices};
 
+/// the font properties a selection depends on
+#[derive(PartialEq, Eq, Hash)]
+struct FontKey {
+    families: Rc<[FontFamily]>,
+    weight: u16,
+    style: FontStyle,
+    /// bits of the stretch, which is never NaN
+    stretch: u32,
+}
+
 #[derive(Clone)]
 pub struct FontCache<'a> {
-    // TODO: use a lock-free map
-    entries: Arc<Mutex<HashMap<String, &'a FontCollection>>>,
-    fallback: &'a FontCollection,
+    entries: Rc<RefCell<HashMap<FontKey, Arc<FontCollection>>>>,
+    database: Option<&'a FontDatabase>,
+    /// the fonts of the `@font-face` rules of the document
+    embedded: Option<Arc<FontDatabase>>,
+    fallback: Arc<FontCollection>,
 }
 impl<'a> fmt::Debug for FontCache<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -21,28 +41,258 @@ impl<'a> fmt::Debug for FontCache<'a> {
     }
 }
 impl<'a> FontCache<'a> {
-    pub fn new(fallback: &'a FontCollection) -> Self {
+    pub fn new(fallback: Arc<FontCollection>) -> Self {
         FontCache {
-            entries: Arc::new(Mutex::new(HashMap::new())),
+            entries: Rc::new(RefCell::new(HashMap::new())),
+            database: None,
+            embedded: None,
             fallback,
         }
     }
-}
+    pub fn set_database(&mut self, database: &'a FontDatabase) {
+        self.database = Some(database);
+        self.entries.borrow_mut().clear();
+    }
+    /// load the fonts defined in the document, by `@font-face` rules and `<font>` elements.
+    /// they are preferred over fonts of the same family in the database.
+    pub fn set_document_fonts(&mut self, svg: &Svg) {
+        let mut database = FontDatabase::new();
+        let mut count = 0;
+        for item in &svg.fonts {
+            if let Item::Font(ref tag) = **item {
+                database.add_font(Font::from_svg(&svg_font_data(tag)));
+                count += 1;
+            }
+        }
+        for face in &svg.font_faces {
+            let font = match face.sources.iter().find_map(load_source) {
+                Some(font) => font,
+                None => {
+                    println!("no usable source for @font-face {:?}", face.family);
+                    continue;
+                }
+            };
+            let own = font.description().clone();
+            let description = FaceDescription {
+                families: vec![face.family.clone()],
+                weight: face.weight.map(|w| w.resolve(400)).unwrap_or(own.weight),
+                stretch: face.stretch.map(|s| s.0).unwrap_or(own.stretch),
+                slant: match face.style {
+                    Some(FontStyle::Normal) => Slant::Normal,
+                    Some(FontStyle::Italic) => Slant::Italic,
+                    Some(FontStyle::Oblique) => Slant::Oblique,
+                    None => own.slant,
+                },
+            };
+            database.add_face(font, description);
+            count += 1;
+        }
+        self.embedded = match count {
+            0 => None,
+            _ => Some(Arc::new(database)),
+        };
+        self.entries.borrow_mut().clear();
+    }
+    /// x-height of the fonts selected by `options` in em
+    pub fn x_height(&self, options: &Options) -> Option<f32> {
+        self.select(options).x_height()
+    }
+    pub fn script_offsets(&self, options: &Options) -> (f32, f32) {
+        self.select(options).script_offsets()
+    }
 
-impl DrawItem for TagText {
-    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        let options = options.apply(scene, &self.attrs);
-        let state = TextState {
-            pos: Vector2F::zero(),
-            rot: 0.0
+    /// fonts matching the font properties of `options`, followed by the fallback fonts
+    pub fn select(&self, options: &Options) -> Arc<FontCollection> {
+        if options.font_family.len() == 0 || (self.database.is_none() && self.embedded.is_none()) {
+            return self.fallback.clone();
+        }
+        let key = FontKey {
+            families: options.font_family.clone(),
+            weight: options.font_weight,
+            style: options.font_style,
+            stretch: options.font_stretch.to_bits(),
         };
+        let mut entries = self.entries.borrow_mut();
+        entries.entry(key).or_insert_with(|| {
+            let families: Vec<FamilyName> = options.font_family.iter().map(|family| match *family {
+                FontFamily::Named(ref name) => FamilyName::Named(name),
+                FontFamily::Generic(generic) => FamilyName::Generic(match generic {
+                    GenericFamily::Serif => Generic::Serif,
+                    GenericFamily::SansSerif => Generic::SansSerif,
+                    GenericFamily::Monospace => Generic::Monospace,
+                    GenericFamily::Cursive => Generic::Cursive,
+                    GenericFamily::Fantasy => Generic::Fantasy,
+                    GenericFamily::SystemUi => Generic::SystemUi,
+                })
+            }).collect();
+            let slant = match options.font_style {
+                FontStyle::Normal => Slant::Normal,
+                FontStyle::Italic => Slant::Italic,
+                FontStyle::Oblique => Slant::Oblique,
+            };
+            let mut fonts = vec![];
+            for family in &families {
+                for database in self.embedded.as_deref().into_iter().chain(self.database) {
+                    fonts.extend(database.select(std::slice::from_ref(family), options.font_weight, slant, options.font_stretch));
+                }
+            }
+            fonts.extend(self.fallback.iter().cloned());
+            Arc::new(FontCollection::from_fonts(fonts))
+        }).clone()
+    }
+}
+
+/// the glyphs and metrics of a `<font>` element
+fn svg_font_data(tag: &TagFont) -> SvgFontData {
+    let face = &tag.font_face;
+    let glyph = |g: &TagGlyph| SvgGlyphData {
+        unicode: g.unicode.as_ref().map(|s| s.chars().collect()).unwrap_or_default(),
+        names: g.names.clone(),
+        outline: g.outline.clone(),
+        advance: g.horiz_adv_x,
+        arabic_form: g.arabic_form.map(|form| match form {
+            ArabicForm::Initial => svg_text::ArabicForm::Initial,
+            ArabicForm::Medial => svg_text::ArabicForm::Medial,
+            ArabicForm::Terminal => svg_text::ArabicForm::Terminal,
+            ArabicForm::Isolated => svg_text::ArabicForm::Isolated,
+        }),
+    };
+    SvgFontData {
+        family: face.family.clone().or_else(|| tag.id.clone()),
+        weight: face.weight.map(|w| w.resolve(400)).unwrap_or(400),
+        slant: match face.style {
+            Some(FontStyle::Italic) => Slant::Italic,
+            Some(FontStyle::Oblique) => Slant::Oblique,
+            _ => Slant::Normal,
+        },
+        units_per_em: face.units_per_em,
+        ascent: face.ascent,
+        descent: face.descent,
+        x_height: face.x_height,
+        cap_height: face.cap_height,
+        underline_position: face.underline_position,
+        underline_thickness: face.underline_thickness,
+        strikeout_position: face.strikethrough_position,
+        strikeout_thickness: face.strikethrough_thickness,
+        advance: tag.horiz_adv_x,
+        missing_glyph: tag.missing_glyph.as_ref().map(glyph),
+        glyphs: tag.glyphs.iter().map(glyph).collect(),
+        kerning: tag.hkern.iter().map(|k| SvgKernPair {
+            u1: k.u1.clone(),
+            g1: k.g1.clone(),
+            u2: k.u2.clone(),
+            g2: k.g2.clone(),
+            k: k.k,
+        }).collect(),
+    }
+}
 
+/// the font of a `@font-face` source, if it is embedded and can be parsed
+fn load_source(source: &FontSource) -> Option<Font> {
+    match *source {
+        FontSource::Data { ref data, .. } => Font::parse(data).map_err(|e| println!("invalid embedded font: {:?}", e)).ok(),
+        FontSource::Url(ref url) => {
+            println!("can't load font from {}", url);
+            None
+        }
+    }
+}
+
+/// elements whose content is laid out as text
+trait TextContent {
+    fn text_attrs(&self) -> &Attrs;
+    /// lays out the content, `options` already has the attributes of the element applied
+    fn layout<'a, O, S>(&self, sink: &mut S, options: &O, font_cache: &FontCache)
+        where O: Deref<Target=Options<'a>> + Clone, S: TextSink<O>;
+}
+impl TextContent for TagText {
+    fn text_attrs(&self) -> &Attrs {
+        &self.attrs
+    }
+    fn layout<'a, O, S>(&self, sink: &mut S, options: &O, font_cache: &FontCache)
+        where O: Deref<Target=Options<'a>> + Clone, S: TextSink<O>
+    {
+        layout_text(sink, options, font_cache, self)
+    }
+}
+impl TextContent for TagTextArea {
+    fn text_attrs(&self) -> &Attrs {
+        &self.attrs
+    }
+    fn layout<'a, O, S>(&self, sink: &mut S, options: &O, font_cache: &FontCache)
+        where O: Deref<Target=Options<'a>> + Clone, S: TextSink<O>
+    {
+        wrap::layout_text_area(sink, options, font_cache, self)
+    }
+}
+
+impl<T: TextContent> Element for T {
+    fn attrs(&self) -> &Attrs {
+        self.text_attrs()
+    }
+    fn bbox(&self, options: &Options) -> Option<RectF> {
+        content_bounds_of(self, options, TextBounds::Cells)
+    }
+    fn content_bounds(&self, options: &BoundsOptions) -> Option<RectF> {
+        options.bounds(content_bounds_of(self, options, TextBounds::Ink)?)
+    }
+    fn overlaps(&self, _options: &Options) -> bool {
+        true
+    }
+    fn draw_content(&self, scene: &mut Scene, options: &DrawOptions) {
+        let mut sink = DrawSink { scene };
         if let Some(ref font_cache) = options.ctx.font_cache {
-            draw_items(scene, &options, font_cache, &self.pos, &self.items, state, 0, None);
+            self.layout(&mut sink, options, font_cache);
+        }
+    }
+}
+
+/// glyph outlines of the text, `options` already has the attributes of the text applied
+fn text_outline(tag: &impl TextContent, options: &Options) -> Option<Outline> {
+    let options = BoundsOptions { common: options.clone(), clip_rect: None };
+    let font_cache = options.ctx.font_cache.as_ref()?;
+    let mut sink = OutlineSink { outline: Outline::new() };
+    tag.layout(&mut sink, &options, font_cache);
+    Some(sink.outline)
+}
+
+/// which boxes make up the bounds of a text
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TextBounds {
+    /// the glyph outlines and decorations
+    Ink,
+    /// the character cells, from the advance and the ascent and descent of the font.
+    /// this is the object bounding box.
+    Cells,
+}
+
+/// bounds of the text in the user space of `options`, which already has the attributes of the text applied
+pub fn text_bounds(tag: &TagText, options: &Options, kind: TextBounds) -> Option<RectF> {
+    content_bounds_of(tag, options, kind)
+}
+
+fn content_bounds_of(tag: &impl TextContent, options: &Options, kind: TextBounds) -> Option<RectF> {
+    let mut options = options.clone();
+    options.set_transform(Transform2F::default());
+    match kind {
+        TextBounds::Ink => text_outline(tag, &options).filter(|o| o.len() > 0).map(|o| o.bounds()),
+        TextBounds::Cells => {
+            let options = BoundsOptions { common: options, clip_rect: None };
+            let font_cache = options.ctx.font_cache.as_ref()?;
+            let mut sink = CellSink { bounds: None };
+            tag.layout(&mut sink, &options, font_cache);
+            sink.bounds
         }
     }
-    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
-        None
+}
+
+impl<T: TextContent> Shape for T {
+    fn outline(&self, options: &Options) -> Option<Outline> {
+        let attrs = self.text_attrs();
+        if !attrs.display.resolve(options) {
+            return None;
+        }
+        text_outline(self, &options.apply(attrs))
     }
 }
 
@@ -50,53 +300,371 @@ impl DrawItem for TagText {
 struct TextState {
     pos: Vector2F,
     rot: f32,
+    /// glyph scale along the inline axis from `lengthAdjust="spacingAndGlyphs"`
+    stretch: f32,
 }
 impl TextState {
+    fn start() -> TextState {
+        TextState {
+            pos: Vector2F::zero(),
+            rot: 0.0,
+            stretch: 1.0,
+        }
+    }
     fn apply_move(self, m: Move) -> TextState {
         let x = m.abs_x.unwrap_or(self.pos.x());
         let y = m.abs_y.unwrap_or(self.pos.y());
         let rot = m.rot.unwrap_or(self.rot);
         TextState {
             pos: vec2f(x, y) + m.rel,
-            rot
+            rot,
+            .. self
+        }
+    }
+}
+
+/// receives the laid out chunks of a text element
+trait TextSink<O> {
+    fn apply(&mut self, options: &O, attrs: &Attrs) -> O;
+    /// the text of the following chunk, which starts at character `first_char` of the element
+    fn text(&mut self, _text: &str, _first_char: usize) {}
+    fn chunk(&mut self, options: &O, font_collection: &FontCollection, layout: &ChunkLayout, state: TextState);
+    /// a decoration line, `rect` is in the coordinate system of the text
+    fn decoration(&mut self, options: &O, rect: RectF);
+}
+
+struct DrawSink<'s> {
+    scene: &'s mut Scene,
+}
+impl<'s, 'a> TextSink<DrawOptions<'a>> for DrawSink<'s> {
+    fn apply(&mut self, options: &DrawOptions<'a>, attrs: &Attrs) -> DrawOptions<'a> {
+        options.apply(self.scene, attrs)
+    }
+    fn chunk(&mut self, options: &DrawOptions<'a>, font_collection: &FontCollection, layout: &ChunkLayout, state: TextState) {
+        draw_layout(font_collection, layout, self.scene, options, state);
+    }
+    fn decoration(&mut self, options: &DrawOptions<'a>, rect: RectF) {
+        options.draw(self.scene, &Outline::from_rect(rect));
+    }
+}
+
+/// collects the glyph outlines in the coordinate system of the text
+struct OutlineSink {
+    outline: Outline,
+}
+impl<'a> TextSink<BoundsOptions<'a>> for OutlineSink {
+    fn apply(&mut self, options: &BoundsOptions<'a>, attrs: &Attrs) -> BoundsOptions<'a> {
+        options.apply(attrs)
+    }
+    fn chunk(&mut self, options: &BoundsOptions<'a>, font_collection: &FontCollection, layout: &ChunkLayout, state: TextState) {
+        for (glyph, tr) in glyph_transforms(layout, font_collection, options, state) {
+            if let Some(g) = font_collection[glyph.font_idx].glyph(glyph.gid) {
+                self.outline.push_outline(g.path.transformed(&(options.transform * tr)));
+            }
         }
     }
+    fn decoration(&mut self, options: &BoundsOptions<'a>, rect: RectF) {
+        self.outline.push_outline(Outline::from_rect(rect).transformed(&options.transform));
+    }
+}
+
+/// the `text-decoration` of a text content element, drawn across all of its text
+/// with the font and paint of that element
+struct Decoration<O> {
+    lines: TextDecoration,
+    options: O,
+    fonts: Arc<FontCollection>,
+}
+
+/// the decorations of the ancestors, followed by the one declared by `attrs`
+fn add_decoration<'a, O>(decorations: &[Rc<Decoration<O>>], attrs: &Attrs, options: &O, font_cache: &FontCache) -> Vec<Rc<Decoration<O>>>
+    where O: Deref<Target=Options<'a>> + Clone
+{
+    let mut decorations = decorations.to_vec();
+    if !attrs.text_decoration.is_none() {
+        decorations.push(Rc::new(Decoration {
+            lines: attrs.text_decoration,
+            options: options.clone(),
+            fonts: font_cache.select(options),
+        }));
+    }
+    decorations
+}
+
+/// collects the union of the character cells
+struct CellSink {
+    bounds: Option<RectF>,
+}
+impl<'a> TextSink<BoundsOptions<'a>> for CellSink {
+    fn apply(&mut self, options: &BoundsOptions<'a>, attrs: &Attrs) -> BoundsOptions<'a> {
+        options.apply(attrs)
+    }
+    fn chunk(&mut self, options: &BoundsOptions<'a>, font_collection: &FontCollection, layout: &ChunkLayout, state: TextState) {
+        let cell = cell_rect(font_collection, options, 0.0, inline(options, layout.advance))
+            + baseline_offset(font_collection, options);
+        let rect = options.transform * text_transform(options, state) * cell;
+        self.bounds = Some(self.bounds.map_or(rect, |b| b.union_rect(rect)));
+    }
+    fn decoration(&mut self, _options: &BoundsOptions<'a>, _rect: RectF) {}
 }
 
-fn chunk(scene: &mut Scene, options: &DrawOptions, s: &str, state: TextState, font_collection: &FontCollection) -> Vector2F {
-    debug!("{} {:?}", s, state);
-    let layout = Chunk::new(s, options.direction).layout(font_collection, options.lang);
-    draw_layout(font_collection, &layout, scene, &options, state)
+/// the cell from `start` to `end` on the inline axis in em, relative to the baseline offset
+fn cell_rect(font_collection: &FontCollection, options: &Options, start: f32, end: f32) -> RectF {
+    let baselines = font_collection.baselines();
+    match options.writing_mode.is_vertical() {
+        false => RectF::from_points(
+            vec2f(start.min(end), -baselines.text_top),
+            vec2f(start.max(end), -baselines.text_bottom)
+        ),
+        // the central baseline is at x = 0 and the top of the glyphs points right
+        true => RectF::from_points(
+            vec2f(baselines.text_bottom - baselines.central, start.min(end)),
+            vec2f(baselines.text_top - baselines.central, start.max(end))
+        ),
+    }
 }
 
-fn draw_items(scene: &mut Scene, options: &DrawOptions, font_cache: &FontCache, pos: &GlyphPos, items: &[Arc<Item>], mut state: TextState, mut char_idx: usize, parent_moves: Option<&Moves>) -> (TextState, usize) {
-    let fallback = &font_cache.fallback;
+/// a laid out part of a text chunk, kept until the chunk is complete and can be anchored
+struct Piece<O> {
+    options: O,
+    fonts: Arc<FontCollection>,
+    text: String,
+    layout_options: LayoutOptions,
+    layout: ChunkLayout,
+    state: TextState,
+    decorations: Vec<Rc<Decoration<O>>>,
+    /// index of the first character in the text element
+    first_char: usize,
+    /// whether the piece starts a new text chunk because of an absolute position
+    chunk_start: bool,
+    /// letter spacing in em that was added by `textLength` but doesn't follow the last cluster
+    trailing: f32,
+}
+impl<'a, O: Deref<Target=Options<'a>> + Clone> Piece<O> {
+    fn new(options: &O, fonts: &Arc<FontCollection>, text: &str, first_char: usize, decorations: &[Rc<Decoration<O>>], state: TextState) -> Self {
+        let layout_options = layout_options(options);
+        let layout = Chunk::new(text, options.direction).layout(fonts, options.lang, &layout_options);
+        Piece {
+            options: options.clone(),
+            fonts: fonts.clone(),
+            text: text.into(),
+            layout_options,
+            layout,
+            state,
+            decorations: decorations.to_vec(),
+            first_char,
+            chunk_start: false,
+            trailing: 0.0,
+        }
+    }
+    /// lays the text out again with `spacing` user units more after each grapheme cluster.
+    /// the `last` piece of a text gets no spacing after its last cluster.
+    fn add_spacing(&mut self, spacing: f32, last: bool) {
+        let em = spacing / (self.options.font_size * self.state.stretch);
+        self.layout_options.letter_spacing += em;
+        if last {
+            self.trailing += em;
+        }
+        self.layout = Chunk::new(&self.text, self.options.direction).layout(&self.fonts, self.options.lang, &self.layout_options);
+        let direction = inline(&self.options, self.layout.advance).signum();
+        self.layout.advance = self.layout.advance - inline_vector(&self.options, direction * self.trailing);
+    }
+}
+impl<'a, O: Deref<Target=Options<'a>>> Piece<O> {
+    /// advance along the inline axis in user units
+    fn advance(&self) -> f32 {
+        inline(&self.options, self.layout.advance) * self.options.font_size * self.state.stretch
+    }
+}
+
+/// the component of `v` along the direction the text advances in
+fn inline(options: &Options, v: Vector2F) -> f32 {
+    match options.writing_mode.is_vertical() {
+        false => v.x(),
+        true => v.y(),
+    }
+}
+/// a vector of length `d` in the direction the text advances in
+fn inline_vector(options: &Options, d: f32) -> Vector2F {
+    match options.writing_mode.is_vertical() {
+        false => vec2f(d, 0.0),
+        true => vec2f(0.0, d),
+    }
+}
+
+fn layout_text<'a, O, S>(sink: &mut S, options: &O, font_cache: &FontCache, tag: &TagText)
+    where O: Deref<Target=Options<'a>> + Clone, S: TextSink<O>
+{
+    let decorations = add_decoration(&[], &tag.attrs, options, font_cache);
+    if let Some(inline_size) = tag.inline_size.map(|l| l.resolve(options)).filter(|&l| l > 0.0) {
+        wrap::layout_inline_size(sink, options, font_cache, tag, &decorations, inline_size);
+        return;
+    }
+
+    let mut pieces = Vec::new();
+    layout_items(sink, options, font_cache, &tag.pos, &tag.items, TextState::start(), 0, None, &decorations, &mut pieces);
+    if let Some(length) = tag.text_length {
+        adjust_length(&mut pieces, 0, length.resolve(options), tag.length_adjust);
+    }
+    // the chunks can only be anchored once the lengths are adjusted
+    let mut chunk = Vec::new();
+    for piece in pieces {
+        if piece.chunk_start {
+            anchor_chunk(sink, &mut chunk);
+        }
+        chunk.push(piece);
+    }
+    anchor_chunk(sink, &mut chunk);
+}
+
+/// shifts the pieces of a text chunk according to the `text-anchor` of its first character
+/// and passes them on to the sink
+fn anchor_chunk<'a, O, S>(sink: &mut S, pieces: &mut Vec<Piece<O>>)
+    where O: Deref<Target=Options<'a>>, S: TextSink<O>
+{
+    if let Some(text_anchor) = pieces.first().map(|first| first.options.text_anchor) {
+        anchor_line(sink, pieces, text_anchor);
+    }
+}
+
+/// shifts the pieces so that the start, middle or end is at the position of the first piece
+/// and passes them on to the sink
+fn anchor_line<'a, O, S>(sink: &mut S, pieces: &mut Vec<Piece<O>>, text_anchor: TextAnchor)
+    where O: Deref<Target=Options<'a>>, S: TextSink<O>
+{
+    let (anchor, direction) = match pieces.first() {
+        Some(first) => (inline(&first.options, first.state.pos), first.options.direction),
+        None => return
+    };
+    let (min, max) = pieces.iter().fold((anchor, anchor), |(min, max), piece| {
+        let start = inline(&piece.options, piece.state.pos);
+        let end = start + piece.advance();
+        (min.min(start).min(end), max.max(start).max(end))
+    });
+
+    // start and end of the chunk in the direction of the text, vertical text always goes down
+    let first = &pieces[0].options;
+    let (start, end) = match (first.writing_mode.is_vertical(), direction) {
+        (false, TextFlow::RightToLeft) => (max, min),
+        _ => (min, max),
+    };
+    let shift = inline_vector(first, match text_anchor {
+        TextAnchor::Start => anchor - start,
+        TextAnchor::Middle => anchor - 0.5 * (start + end),
+        TextAnchor::End => anchor - end,
+    });
+
+    for piece in pieces.drain(..) {
+        let state = TextState { pos: piece.state.pos + shift, .. piece.state };
+        // underlines and overlines are painted below the text, line-throughs above it
+        draw_decorations(sink, &piece, state, false);
+        sink.text(&piece.text, piece.first_char);
+        sink.chunk(&piece.options, &piece.fonts, &piece.layout, state);
+        draw_decorations(sink, &piece, state, true);
+    }
+}
+
+/// the decoration lines of a piece, either the line-throughs (`above`) or the underlines and overlines
+fn draw_decorations<'a, O, S>(sink: &mut S, piece: &Piece<O>, state: TextState, above: bool)
+    where O: Deref<Target=Options<'a>>, S: TextSink<O>
+{
+    let vertical = piece.options.writing_mode.is_vertical();
+    let start = inline(&piece.options, state.pos);
+    let end = start + piece.advance();
+    if start == end {
+        return;
+    }
+    for decoration in &piece.decorations {
+        let metrics = decoration.fonts.decorations();
+        let pick = |enabled: bool, line: DecorationLine| Some(line).filter(|_| enabled);
+        let lines = match above {
+            true => [pick(decoration.lines.line_through, metrics.line_through), None],
+            false => [pick(decoration.lines.underline, metrics.underline), pick(decoration.lines.overline, metrics.overline)],
+        };
+
+        // the lines are relative to the alphabetic baseline of the element that declared them
+        let font_size = decoration.options.font_size;
+        let offset = baseline_offset(&decoration.fonts, &decoration.options);
+        for line in lines.iter().flatten() {
+            let half = 0.5 * line.thickness * font_size;
+            let rect = match vertical {
+                false => {
+                    let center = state.pos.y() + (offset.y() - line.position) * font_size;
+                    RectF::from_points(vec2f(start.min(end), center - half), vec2f(start.max(end), center + half))
+                }
+                // the top of the glyphs points right
+                true => {
+                    let central = decoration.fonts.baselines().central;
+                    let center = state.pos.x() + (offset.x() - central + line.position) * font_size;
+                    RectF::from_points(vec2f(center - half, start.min(end)), vec2f(center + half, start.max(end)))
+                }
+            };
+            sink.decoration(&decoration.options, rect);
+        }
+    }
+}
+
+fn layout_items<'a, O, S>(sink: &mut S, options: &O, font_cache: &FontCache, pos: &GlyphPos, items: &[Arc<Item>], mut state: TextState, mut char_idx: usize, parent_moves: Option<&Moves>, decorations: &[Rc<Decoration<O>>], pieces: &mut Vec<Piece<O>>) -> (TextState, usize)
+    where O: Deref<Target=Options<'a>> + Clone, S: TextSink<O>
+{
+    let fonts = font_cache.select(options);
     let moves = Moves::new(pos, char_idx, parent_moves);
 
+    let chunk = |pieces: &mut Vec<Piece<O>>, s: &str, first_char: usize, state: TextState, chunk_start: bool| -> Vector2F {
+        debug!("{} {:?}", s, state);
+        let mut piece = Piece::new(options, &fonts, s, first_char, decorations, state);
+        piece.chunk_start = chunk_start;
+        let advance = piece.layout.advance * options.font_size;
+        pieces.push(piece);
+        advance
+    };
+
     for item in items.iter() {
         match **item {
             Item::String(ref s) if s.len() > 0 => {
                 let mut start = 0;
+                let mut start_char = char_idx;
+                let mut chunk_start = false;
                 for (idx, grapheme) in GraphemeIndices::new(s) {
                     let num_chars = grapheme.chars().count();
-                    if let Some(next_move) = moves.get(&options, num_chars, char_idx) {
+                    if let Some(next_move) = moves.get(options, num_chars, char_idx) {
                         if idx > 0 {
-                            state.pos = state.pos + chunk(scene, options, &s[start .. idx], state, fallback);
+                            state.pos = state.pos + chunk(pieces, &s[start .. idx], start_char, state, chunk_start);
                         }
+                        // an absolute position starts a new text chunk
+                        chunk_start = next_move.abs_x.is_some() || next_move.abs_y.is_some();
                         start = idx;
+                        start_char = char_idx;
                         state = state.apply_move(next_move);
-                        char_idx += num_chars;
                     }
+                    char_idx += num_chars;
                 }
 
                 let part = &s[start ..];
-                let num_chars = part.chars().count();
-                state.pos = state.pos + chunk(scene, options, part, state, fallback);
-                char_idx += num_chars;
+                state.pos = state.pos + chunk(pieces, part, start_char, state, chunk_start);
             },
             Item::TSpan(ref span) => {
-                let options = options.apply(scene, &span.attrs);
-                let (new_state, new_idx) = draw_items(scene, &options, font_cache, &span.pos, &span.items, state, char_idx, Some(&moves));
+                let options = sink.apply(options, &span.attrs);
+                let start = pieces.len();
+                let decorations = add_decoration(decorations, &span.attrs, &options, font_cache);
+                let (new_state, new_idx) = layout_items(sink, &options, font_cache, &span.pos, &span.items, state, char_idx, Some(&moves), &decorations, pieces);
+                state = new_state;
+                char_idx = new_idx;
+                if let Some(length) = span.text_length {
+                    let delta = adjust_length(pieces, start, length.resolve(&*options), span.length_adjust);
+                    state.pos = state.pos + inline_vector(&options, delta);
+                }
+            }
+            Item::TRef(ref tref) => {
+                let text = match tref_text(tref, options) {
+                    Some(text) => text,
+                    None => continue
+                };
+                // the referenced text is laid out like the content of a tspan
+                let options = sink.apply(options, &tref.attrs);
+                let items = [Arc::new(Item::String(text))];
+                let decorations = add_decoration(decorations, &tref.attrs, &options, font_cache);
+                let (new_state, new_idx) = layout_items(sink, &options, font_cache, &GlyphPos::default(), &items, state, char_idx, Some(&moves), &decorations, pieces);
                 state = new_state;
                 char_idx = new_idx;
             }
@@ -107,22 +675,150 @@ fn draw_items(scene: &mut Scene, options: &DrawOptions, font_cache: &FontCache,
     (state, char_idx)
 }
 
-fn draw_layout(font_collection: &FontCollection, layout: &ChunkLayout, scene: &mut Scene, options: &DrawOptions, state: TextState) -> Vector2F {
-    for &(_, offset, ref sublayout) in &layout.parts {
-        for glyph in &sublayout.glyphs {
-            let chunk_tr = Transform2F::from_translation(state.pos) * Transform2F::from_rotation(deg2rad(state.rot))
-                * Transform2F::from_scale(options.font_size)
-                * Transform2F::from_translation(offset + glyph.offset);
-            let tr = chunk_tr * glyph.transform;
+fn layout_options(options: &Options) -> LayoutOptions {
+    LayoutOptions {
+        letter_spacing: options.letter_spacing / options.font_size,
+        word_spacing: options.word_spacing / options.font_size,
+        kerning: options.font_kerning,
+        vertical: options.writing_mode.is_vertical(),
+        orientation: match options.text_orientation {
+            TextOrientation::Mixed => Orientation::Mixed,
+            TextOrientation::Upright => Orientation::Upright,
+            TextOrientation::Sideways => Orientation::Sideways,
+        },
+    }
+}
+
+/// the character data of the element a `<tref>` refers to
+fn tref_text(tref: &TagTRef, options: &Options) -> Option<String> {
+    match tref.href.as_ref().and_then(|href| options.ctx.resolve_href(href)) {
+        Some(item) => Some(text_content(item)),
+        None => {
+            println!("can't resolve <tref href={:?}>", tref.href);
+            None
+        }
+    }
+}
+
+/// changes the extent of the pieces from `start` on to `length` (`textLength`) and returns
+/// by how much the following text has to move.
+/// the extent reaches from the lowest to the highest inline position of the pieces,
+/// so it includes pieces after an absolute position.
+fn adjust_length<'a, O>(pieces: &mut [Piece<O>], start: usize, length: f32, adjust: LengthAdjust) -> f32
+    where O: Deref<Target=Options<'a>> + Clone
+{
+    let pieces = &mut pieces[start.min(pieces.len()) ..];
+    let (min, max, end) = match pieces.last() {
+        Some(last) => {
+            let end = inline(&last.options, last.state.pos) + last.advance();
+            pieces.iter().fold((end, end, end), |(min, max, end), piece| {
+                let start = inline(&piece.options, piece.state.pos);
+                let stop = start + piece.advance();
+                (min.min(start).min(stop), max.max(start).max(stop), end)
+            })
+        }
+        None => return 0.0
+    };
+    if max <= min || length <= 0.0 {
+        return 0.0;
+    }
+
+    match adjust {
+        LengthAdjust::SpacingAndGlyphs => {
+            // positions and glyphs are scaled away from the start of the extent
+            let scale = length / (max - min);
+            for piece in pieces.iter_mut() {
+                let offset = inline(&piece.options, piece.state.pos) - min;
+                piece.state.pos = piece.state.pos + inline_vector(&piece.options, offset * (scale - 1.0));
+                piece.state.stretch *= scale;
+            }
+            (end - min) * (scale - 1.0)
+        }
+        LengthAdjust::Spacing => {
+            // the extra space goes between the characters, not after the last one
+            let clusters: usize = pieces.iter().map(|piece| GraphemeIndices::new(&piece.text).count()).sum();
+            if clusters < 2 {
+                return 0.0;
+            }
+            let spacing = (length - (max - min)) / (clusters - 1) as f32;
+            let last = pieces.len() - 1;
+            let mut shift = 0.0;
+            for (i, piece) in pieces.iter_mut().enumerate() {
+                piece.state.pos = piece.state.pos + inline_vector(&piece.options, shift);
+                let advance = piece.advance();
+                piece.add_spacing(spacing, i == last);
+                shift += piece.advance() - advance;
+            }
+            shift
+        }
+    }
+}
+
+/// offset of the glyphs in em, so that the selected baseline is at the current text position
+/// and the baseline shift is applied. vertical text is laid out centered on the central baseline
+/// with the top of the glyphs pointing right.
+fn baseline_offset(font_collection: &FontCollection, options: &Options) -> Vector2F {
+    let baselines = font_collection.baselines();
+    let vertical = options.writing_mode.is_vertical();
+    let baseline = match options.alignment_baseline {
+        Baseline::Auto => options.dominant_baseline,
+        baseline => baseline
+    };
+    let position = match baseline {
+        Baseline::Auto if vertical => baselines.central,
+        Baseline::Auto | Baseline::Alphabetic => baselines.alphabetic,
+        Baseline::Ideographic => baselines.ideographic,
+        Baseline::Hanging => baselines.hanging,
+        Baseline::Mathematical => baselines.mathematical,
+        Baseline::Central => baselines.central,
+        Baseline::Middle => baselines.middle,
+        Baseline::TextTop => baselines.text_top,
+        Baseline::TextBottom => baselines.text_bottom,
+    };
+    let shift = options.baseline_shift / options.font_size;
+    match vertical {
+        false => vec2f(0.0, position - shift),
+        true => vec2f(baselines.central - position + shift, 0.0),
+    }
+}
+
+/// from the em space of a piece at `state` to the coordinate system of the text
+fn text_transform(options: &Options, state: TextState) -> Transform2F {
+    let scale = match options.writing_mode.is_vertical() {
+        false => vec2f(options.font_size * state.stretch, options.font_size),
+        true => vec2f(options.font_size, options.font_size * state.stretch),
+    };
+    Transform2F::from_translation(state.pos) * Transform2F::from_rotation(deg2rad(state.rot))
+        * Transform2F::from_scale(scale)
+}
+
+/// glyphs of the layout with their transform relative to the text
+fn glyph_transforms<'l>(layout: &'l ChunkLayout, font_collection: &FontCollection, options: &Options, state: TextState) -> impl Iterator<Item=(&'l LayoutGlyph, Transform2F)> + 'l {
+    let tr = text_transform(options, state);
+    let baseline = baseline_offset(font_collection, options);
+    layout.parts.iter().flat_map(move |&(_, offset, ref sublayout)| {
+        sublayout.glyphs.iter().map(move |glyph| {
+            let chunk_tr = tr * Transform2F::from_translation(offset + glyph.offset + baseline);
+            (glyph, chunk_tr * glyph.transform)
+        })
+    })
+}
+
+fn draw_layout(font_collection: &FontCollection, layout: &ChunkLayout, scene: &mut Scene, options: &DrawOptions, state: TextState) {
+    // each layer is painted for all glyphs of the chunk before the next one,
+    // so a stroke painted first does not cover the fill of the neighbouring glyphs
+    for &layer in options.paint_order.layers() {
+        for (glyph, tr) in glyph_transforms(layout, font_collection, options, state) {
             let font = &font_collection[glyph.font_idx];
             if let Some(ref svg) = font.svg_glyph(glyph.gid) {
-                draw_glyph(svg, scene, tr);
+                if layer == PaintLayer::Fill && options.is_visible() {
+                    draw_glyph(svg, scene, tr);
+                }
             } else {
-                options.draw_transformed(scene, &font.glyph(glyph.gid).unwrap().path, tr);
+                options.draw_layer(scene, &font.glyph(glyph.gid).unwrap().path, tr, layer);
             }
         }
     }
-    layout.advance * options.font_size
 }
 
 fn slice<T>(o: &Option<OneOrMany<T>>) -> &[T] {
@@ -166,7 +862,7 @@ impl<'a> Moves<'a> {
     fn rotate(&self, idx: usize) -> Option<f32> {
         self.rotate.get(idx - self.offset).or(self.rotate.last()).cloned().or_else(|| self.parent.and_then(|p| p.rotate(idx)))
     }
-    fn get<'o>(&self, options: &DrawOptions<'o>, num_chars: usize, idx: usize) -> Option<Move> {
+    fn get(&self, options: &Options, num_chars: usize, idx: usize) -> Option<Move> {
         let rel = |dx: Option<LengthX>, dy: Option<LengthY>| {
             let dx2: f32 = (idx + 1 .. idx + num_chars).flat_map(|idx| self.dx(idx).map(|l| l.resolve(options))).sum();
             let dy2: f32 = (idx + 1 .. idx + num_chars).flat_map(|idx| self.dy(idx).map(|l| l.resolve(options))).sum();
@@ -199,4 +895,104 @@ struct Move {
     abs_y: Option<f32>,
     rel: Vector2F,
     rot: Option<f32>
-}
\ No newline at end of file
+}
+/// calls `f` with the text with the id `t` and its options, which use the test font
+#[cfg(test)]
+fn with_test_text<R>(text: &str, f: impl FnOnce(&TagText, &Options) -> R) -> R {
+    let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg">{}</svg>"#, text)).unwrap();
+    let fonts = FontCollection::from_font(Font::load(include_bytes!("../../../resources/latinmodern-math.otf")));
+    let ctx = DrawContext::new(&svg, Arc::new(fonts));
+    let options = Options::new(&ctx);
+    match **svg.get_item("t").unwrap() {
+        Item::Text(ref tag) => f(tag, &options.apply(&tag.attrs)),
+        _ => unreachable!()
+    }
+}
+
+/// the geometry of the text with the id `t`, laid out with the test font
+#[cfg(test)]
+fn test_geometry(text: &str) -> TextGeometry {
+    with_test_text(text, |tag, options| text_geometry(tag, options).unwrap())
+}
+
+#[test]
+fn test_text_bounds() {
+    let bounds = |text: &str| with_test_text(text, |tag, options| {
+        (text_bounds(tag, options, TextBounds::Ink), text_bounds(tag, options, TextBounds::Cells))
+    });
+    let advance = test_geometry(r#"<text id="t" font-size="10">ab</text>"#).end_position_of_char(1).unwrap().x();
+
+    // the cells span the advance and contain the glyphs
+    let (ink, cells) = bounds(r#"<text id="t" font-size="10">ab</text>"#);
+    let (ink, cells) = (ink.unwrap(), cells.unwrap());
+    assert!(cells.min_x().abs() < 1e-3 && (cells.max_x() - advance).abs() < 1e-3);
+    assert!(cells.min_y() < 0.0 && cells.max_y() > 0.0);
+    assert!(cells.dilate(1e-3).contains_rect(ink));
+
+    // decorations are ink across the whole advance
+    let (underlined, _) = bounds(r#"<text id="t" font-size="10" text-decoration="underline">ab</text>"#);
+    let underlined = underlined.unwrap();
+    assert!(underlined.min_x() < 1e-3 && underlined.max_x() > advance - 1e-3);
+    assert!(underlined.max_y() > ink.max_y());
+
+    // a space has a cell, but no ink
+    let (space_ink, space_cells) = bounds(r#"<text id="t" font-size="10" xml:space="preserve"> </text>"#);
+    assert!(space_ink.is_none());
+    assert!(space_cells.unwrap().width() > 0.0);
+
+    // the bounds are in the user space of the text, which is transformed by x and y
+    let (_, moved) = bounds(r#"<text id="t" font-size="10" x="100" y="50">ab</text>"#);
+    assert!((moved.unwrap().origin() - cells.origin() - vec2f(100., 50.)).length() < 1e-3);
+}
+
+#[test]
+fn test_text_length() {
+    let x = |geometry: &TextGeometry, idx: usize| geometry.start_position_of_char(idx).unwrap().x();
+    let plain = test_geometry(r#"<text id="t" font-size="10">abc</text>"#);
+    let width = |idx: usize| plain.end_position_of_char(idx).unwrap().x() - x(&plain, idx);
+    let total = plain.end_position_of_char(2).unwrap().x();
+
+    // the glyphs keep their size and the rest is spread evenly between them
+    let spaced = test_geometry(r#"<text id="t" font-size="10" textLength="100" lengthAdjust="spacing">abc</text>"#);
+    let gap = x(&spaced, 1) - x(&spaced, 0) - width(0);
+    assert!(gap > 0.0);
+    assert!((x(&spaced, 2) - x(&spaced, 1) - width(1) - gap).abs() < 1e-3);
+    assert!((x(&spaced, 2) + width(2) - 100.).abs() < 1e-3);
+
+    // positions and glyphs are scaled
+    let scaled = test_geometry(r#"<text id="t" font-size="10" textLength="100" lengthAdjust="spacingAndGlyphs">abc</text>"#);
+    for idx in 0 .. 3 {
+        assert!((x(&scaled, idx) - x(&plain, idx) * 100. / total).abs() < 1e-3);
+    }
+    assert!((scaled.end_position_of_char(2).unwrap().x() - 100.).abs() < 1e-3);
+}
+
+#[test]
+fn test_text_length_chunks() {
+    let x = |geometry: &TextGeometry, idx: usize| geometry.start_position_of_char(idx).unwrap().x();
+    let plain = test_geometry(r#"<text id="t" font-size="10">ab<tspan x="100">cd</tspan></text>"#);
+    let end = plain.end_position_of_char(3).unwrap().x();
+
+    // the text after an absolute position is adjusted as well
+    let scaled = test_geometry(r#"<text id="t" font-size="10" textLength="200" lengthAdjust="spacingAndGlyphs">ab<tspan x="100">cd</tspan></text>"#);
+    assert!((x(&scaled, 2) - 100. * 200. / end).abs() < 1e-3);
+    assert!((scaled.end_position_of_char(3).unwrap().x() - 200.).abs() < 1e-3);
+}
+
+#[test]
+fn test_tspan_length() {
+    let x = |geometry: &TextGeometry, idx: usize| geometry.start_position_of_char(idx).unwrap().x();
+    let plain = test_geometry(r#"<text id="t" font-size="10">ab<tspan>cd</tspan>ef</text>"#);
+    let geometry = test_geometry(r#"<text id="t" font-size="10">ab<tspan textLength="50">cd</tspan>ef</text>"#);
+
+    // the text before the tspan stays, the text after it follows the adjusted length
+    assert!((x(&geometry, 2) - x(&plain, 2)).abs() < 1e-3);
+    assert!((x(&geometry, 4) - x(&geometry, 2) - 50.).abs() < 1e-3);
+    assert!((x(&geometry, 5) - x(&geometry, 4) - (x(&plain, 5) - x(&plain, 4))).abs() < 1e-3);
+
+    // the length of the text includes the adjusted tspan
+    let nested = test_geometry(r#"<text id="t" font-size="10" textLength="200">ab<tspan textLength="50">cd</tspan>ef</text>"#);
+    let width = plain.end_position_of_char(5).unwrap().x() - x(&plain, 5);
+    assert!((x(&nested, 5) + width - 200.).abs() < 1e-3);
+    assert!(x(&nested, 4) - x(&nested, 2) > 50.);
+}
@@ -0,0 +1,268 @@
+//! line wrapping for `inline-size` and `<textArea>`
+
+use super::*;
+use unicode_linebreak::linebreaks;
+
+/// text with the same options, or a forced line break
+enum Content<O> {
+    Text {
+        options: O,
+        fonts: Arc<FontCollection>,
+        decorations: Vec<Rc<Decoration<O>>>,
+        text: String,
+        first_char: usize,
+    },
+    Break,
+}
+
+/// the text of `items` in order. positioning attributes of the descendants are ignored in wrapped text.
+/// `char_idx` counts the characters so far.
+fn collect<'a, O, S>(sink: &mut S, options: &O, font_cache: &FontCache, items: &[Arc<Item>], decorations: &[Rc<Decoration<O>>], char_idx: &mut usize, out: &mut Vec<Content<O>>)
+    where O: Deref<Target=Options<'a>> + Clone, S: TextSink<O>
+{
+    for item in items {
+        match **item {
+            Item::String(ref s) => {
+                out.push(Content::Text {
+                    options: options.clone(),
+                    fonts: font_cache.select(options),
+                    decorations: decorations.to_vec(),
+                    text: s.clone(),
+                    first_char: *char_idx,
+                });
+                *char_idx += s.chars().count();
+            }
+            Item::TSpan(ref span) => {
+                let options = sink.apply(options, &span.attrs);
+                let decorations = add_decoration(decorations, &span.attrs, &options, font_cache);
+                collect(sink, &options, font_cache, &span.items, &decorations, char_idx, out);
+            }
+            Item::TRef(ref tref) => {
+                if let Some(text) = tref_text(tref, options) {
+                    let options = sink.apply(options, &tref.attrs);
+                    let decorations = add_decoration(decorations, &tref.attrs, &options, font_cache);
+                    let first_char = *char_idx;
+                    *char_idx += text.chars().count();
+                    out.push(Content::Text { fonts: font_cache.select(&options), options, decorations, text, first_char });
+                }
+            }
+            Item::TBreak(_) => out.push(Content::Break),
+            _ => {}
+        }
+    }
+}
+
+/// byte offsets in `s` where a line may break, from the line breaking algorithm of UAX #14.
+/// forced breaks are treated like other opportunities, as newlines are already spaces.
+fn break_opportunities(s: &str) -> Vec<usize> {
+    linebreaks(s).map(|(idx, _)| idx).filter(|&idx| idx < s.len()).collect()
+}
+
+/// the advance of the whitespace at the end of the piece
+fn trailing_space<'a, O>(piece: &Piece<O>) -> f32
+    where O: Deref<Target=Options<'a>> + Clone
+{
+    let trimmed = piece.text.trim_end();
+    if trimmed.len() == piece.text.len() {
+        return 0.0;
+    }
+    let trimmed = Piece::new(&piece.options, &piece.fonts, trimmed, piece.first_char, &piece.decorations, piece.state);
+    piece.advance().abs() - trimmed.advance().abs()
+}
+
+/// the piece without the whitespace at its end, `None` if nothing is left
+fn trim_end<'a, O>(piece: Piece<O>) -> Option<Piece<O>>
+    where O: Deref<Target=Options<'a>> + Clone
+{
+    let trimmed = piece.text.trim_end();
+    if trimmed.len() == piece.text.len() {
+        Some(piece)
+    } else if trimmed.len() == 0 {
+        None
+    } else {
+        Some(Piece::new(&piece.options, &piece.fonts, trimmed, piece.first_char, &piece.decorations, piece.state))
+    }
+}
+
+/// breaks words into lines of at most `max` length, unless a word is longer
+struct Lines<T> {
+    lines: Vec<Vec<T>>,
+    width: f32,
+    max: f32,
+}
+impl<T> Lines<T> {
+    fn new(max: f32) -> Self {
+        Lines { lines: vec![vec![]], width: 0.0, max }
+    }
+    /// adds the parts of a word that is `width` long, including `trailing` whitespace at its end
+    fn add_word(&mut self, word: &mut Vec<T>, width: f32, trailing: f32) {
+        if word.len() == 0 {
+            return;
+        }
+        // spaces at the end of the line hang and don't need to fit
+        if self.width + width - trailing > self.max && self.lines.last().unwrap().len() > 0 {
+            self.new_line();
+        }
+        self.width += width;
+        self.lines.last_mut().unwrap().extend(word.drain(..));
+    }
+    fn new_line(&mut self) {
+        self.lines.push(vec![]);
+        self.width = 0.0;
+    }
+}
+
+/// lays out the content in lines of at most `max` length along the inline axis.
+/// the first line is anchored at `origin`, each following one is moved by `line_advance`.
+/// at most `max_lines` lines are passed to the sink.
+fn layout_lines<'a, O, S>(sink: &mut S, content: Vec<Content<O>>, max: f32, origin: Vector2F, line_advance: Vector2F, text_anchor: TextAnchor, max_lines: Option<usize>)
+    where O: Deref<Target=Options<'a>> + Clone, S: TextSink<O>
+{
+    let mut lines = Lines::new(max);
+    let mut word = vec![];
+    let add_word = |lines: &mut Lines<Piece<O>>, word: &mut Vec<Piece<O>>| {
+        let width: f32 = word.iter().map(|piece| piece.advance().abs()).sum();
+        let trailing = word.last().map(trailing_space).unwrap_or(0.0);
+        lines.add_word(word, width, trailing);
+    };
+    for content in content {
+        let (options, fonts, decorations, text, first_char) = match content {
+            Content::Text { options, fonts, decorations, text, first_char } => (options, fonts, decorations, text, first_char),
+            Content::Break => {
+                add_word(&mut lines, &mut word);
+                lines.new_line();
+                continue;
+            }
+        };
+        // a word ends at the boundary of two elements if there is a space
+        if text.starts_with(char::is_whitespace) {
+            add_word(&mut lines, &mut word);
+        }
+        let mut start = 0;
+        let mut start_char = first_char;
+        for end in break_opportunities(&text).into_iter().chain(Some(text.len())) {
+            if end > start {
+                let part = &text[start .. end];
+                word.push(Piece::new(&options, &fonts, part, start_char, &decorations, TextState::start()));
+                start_char += part.chars().count();
+            }
+            if end < text.len() || text.ends_with(char::is_whitespace) {
+                add_word(&mut lines, &mut word);
+            }
+            start = end;
+        }
+    }
+    add_word(&mut lines, &mut word);
+
+    let count = max_lines.unwrap_or(usize::MAX);
+    for (i, line) in lines.lines.into_iter().take(count).enumerate() {
+        let mut line: Vec<Piece<O>> = line.into_iter().collect();
+        if let Some(last) = line.pop() {
+            line.extend(trim_end(last));
+        }
+        let mut pos = origin + line_advance * i as f32;
+        for piece in line.iter_mut() {
+            piece.state.pos = pos;
+            pos = pos + inline_vector(&piece.options, piece.advance());
+        }
+        anchor_line(sink, &mut line, text_anchor);
+    }
+}
+
+/// distance between the baselines of two lines
+fn line_height(fonts: &FontCollection, options: &Options) -> f32 {
+    let baselines = fonts.baselines();
+    (baselines.text_top - baselines.text_bottom) * options.font_size
+}
+
+/// the direction the lines follow each other in
+fn block_vector(options: &Options, d: f32) -> Vector2F {
+    match options.writing_mode {
+        WritingMode::HorizontalTb => vec2f(0.0, d),
+        WritingMode::VerticalRl => vec2f(-d, 0.0),
+        WritingMode::VerticalLr => vec2f(d, 0.0),
+    }
+}
+
+/// a `<text>` with `inline-size`, wrapped at the first position and anchored by `text-anchor`
+pub(super) fn layout_inline_size<'a, O, S>(sink: &mut S, options: &O, font_cache: &FontCache, tag: &TagText, decorations: &[Rc<Decoration<O>>], inline_size: f32)
+    where O: Deref<Target=Options<'a>> + Clone, S: TextSink<O>
+{
+    let mut content = vec![];
+    collect(sink, options, font_cache, &tag.items, decorations, &mut 0, &mut content);
+
+    let x = slice(&tag.pos.x).first().map(|l| l.resolve(options)).unwrap_or(0.0);
+    let y = slice(&tag.pos.y).first().map(|l| l.resolve(options)).unwrap_or(0.0);
+    let line_advance = block_vector(options, line_height(&font_cache.select(options), options));
+    layout_lines(sink, content, inline_size, vec2f(x, y), line_advance, options.text_anchor, None);
+}
+
+/// a `<textArea>`, aligned by `text-align`. lines that don't fit in the height are not drawn.
+pub(super) fn layout_text_area<'a, O, S>(sink: &mut S, options: &O, font_cache: &FontCache, tag: &TagTextArea)
+    where O: Deref<Target=Options<'a>> + Clone, S: TextSink<O>
+{
+    let decorations = add_decoration(&[], &tag.attrs, options, font_cache);
+    let mut content = vec![];
+    collect(sink, options, font_cache, &tag.items, &decorations, &mut 0, &mut content);
+
+    let rtl = matches!(options.direction, TextFlow::RightToLeft);
+    let text_anchor = match (options.text_align, rtl) {
+        (TextAlign::Start, _) | (TextAlign::Left, false) | (TextAlign::Right, true) => TextAnchor::Start,
+        (TextAlign::Center, _) => TextAnchor::Middle,
+        _ => TextAnchor::End,
+    };
+    let x = tag.x.resolve(options);
+    let width = tag.width.map(|w| w.resolve(options));
+    let anchor_x = match (width, text_anchor, rtl) {
+        (None, _, _) => x,
+        (Some(_), TextAnchor::Start, false) | (Some(_), TextAnchor::End, true) => x,
+        (Some(w), TextAnchor::Middle, _) => x + 0.5 * w,
+        (Some(w), _, _) => x + w,
+    };
+
+    // the top of the first line is at y
+    let fonts = font_cache.select(options);
+    let line_height = line_height(&fonts, options);
+    let first_baseline = tag.y.resolve(options) + fonts.baselines().text_top * options.font_size;
+    let max_lines = match tag.height.map(|h| h.resolve(options)) {
+        Some(height) if line_height > 0.0 => Some((height / line_height).floor().max(0.0) as usize),
+        _ => None
+    };
+    layout_lines(sink, content, width.unwrap_or(f32::INFINITY), vec2f(anchor_x, first_baseline), vec2f(0.0, line_height), text_anchor, max_lines);
+}
+
+#[test]
+fn test_break_opportunities() {
+    assert_eq!(break_opportunities("hello world"), [6]);
+    assert_eq!(break_opportunities("a  b"), [3]);
+    assert_eq!(break_opportunities("well-known"), [5]);
+    assert_eq!(break_opportunities("(1.5)"), Vec::<usize>::new());
+    // between ideographs, but not before closing punctuation
+    assert_eq!(break_opportunities("中文。字"), [3, 9]);
+    // a newline is an opportunity like a space
+    assert_eq!(break_opportunities("a\nb"), [2]);
+}
+
+#[test]
+fn test_lines() {
+    let mut lines = Lines::new(10.);
+    lines.add_word(&mut vec!["aaaa "], 5., 1.);
+    lines.add_word(&mut vec!["bbbbb"], 5., 0.);
+    lines.add_word(&mut vec!["cc "], 3., 1.);
+    // a word that is longer than a line gets a line of its own
+    lines.add_word(&mut vec!["dddddddddddd"], 12., 0.);
+    lines.add_word(&mut vec![], 0., 0.);
+    lines.new_line();
+    lines.add_word(&mut vec!["e"], 1., 0.);
+    assert_eq!(lines.lines, [vec!["aaaa ", "bbbbb"], vec!["cc "], vec!["dddddddddddd"], vec!["e"]]);
+}
+
+#[test]
+fn test_hanging_space() {
+    // the space at the end of the line doesn't need to fit
+    let mut lines = Lines::new(10.);
+    lines.add_word(&mut vec!["aaaa"], 4., 0.);
+    lines.add_word(&mut vec!["bbbbb", " "], 7., 1.);
+    lines.add_word(&mut vec!["c"], 1., 0.);
+    assert_eq!(lines.lines, [vec!["aaaa", "bbbbb", " "], vec!["c"]]);
+}
@@ -0,0 +1,285 @@
+//! selection of fonts by family, weight, slant and stretch, following the CSS font matching rules
+
+use crate::Font;
+use crate::metrics::{table, u16_at};
+use std::collections::HashMap;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Slant {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Generic {
+    Serif,
+    SansSerif,
+    Monospace,
+    Cursive,
+    Fantasy,
+    SystemUi,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum FamilyName<'a> {
+    Named(&'a str),
+    Generic(Generic),
+}
+
+/// how a face describes itself
+#[derive(Debug, Clone)]
+pub struct FaceDescription {
+    /// typographic and legacy family names
+    pub families: Vec<String>,
+    /// 1 to 1000, 400 is normal
+    pub weight: u16,
+    /// width in percent, 100 is normal
+    pub stretch: f32,
+    pub slant: Slant,
+}
+impl Default for FaceDescription {
+    fn default() -> Self {
+        FaceDescription {
+            families: vec![],
+            weight: 400,
+            stretch: 100.,
+            slant: Slant::Normal,
+        }
+    }
+}
+impl FaceDescription {
+    /// read the description from the name, OS/2 and head tables
+    pub fn parse(data: &[u8]) -> FaceDescription {
+        let mut desc = FaceDescription::default();
+        if let Some(name) = table(data, b"name") {
+            // typographic family first, it groups more faces than the legacy one
+            for &id in &[16, 1] {
+                if let Some(family) = name_string(name, id) {
+                    if !desc.families.contains(&family) {
+                        desc.families.push(family);
+                    }
+                }
+            }
+        }
+        if let Some(os2) = table(data, b"OS/2") {
+            desc.weight = match u16_at(os2, 4).unwrap_or(400) {
+                // some old fonts use 1 to 9
+                w @ 1 ..= 9 => w * 100,
+                w => w.max(1).min(1000)
+            };
+            desc.stretch = match u16_at(os2, 6).unwrap_or(5) {
+                1 => 50.,
+                2 => 62.5,
+                3 => 75.,
+                4 => 87.5,
+                6 => 112.5,
+                7 => 125.,
+                8 => 150.,
+                9 => 200.,
+                _ => 100.
+            };
+            let selection = u16_at(os2, 62).unwrap_or(0);
+            desc.slant = if selection & (1 << 9) != 0 {
+                Slant::Oblique
+            } else if selection & 1 != 0 {
+                Slant::Italic
+            } else {
+                Slant::Normal
+            };
+        } else if let Some(head) = table(data, b"head") {
+            let mac_style = u16_at(head, 44).unwrap_or(0);
+            if mac_style & 1 != 0 {
+                desc.weight = 700;
+            }
+            if mac_style & 2 != 0 {
+                desc.slant = Slant::Italic;
+            }
+        }
+        desc
+    }
+}
+
+/// a name from the name table, preferring english names
+fn name_string(name: &[u8], name_id: u16) -> Option<String> {
+    let count = u16_at(name, 2)? as usize;
+    let storage = name.get(u16_at(name, 4)? as usize ..)?;
+
+    let mut best: Option<(u8, String)> = None;
+    for record in (0 .. count).map(|i| 6 + 12 * i) {
+        if u16_at(name, record + 6)? != name_id {
+            continue;
+        }
+        let platform = u16_at(name, record)?;
+        let language = u16_at(name, record + 4)?;
+        let length = u16_at(name, record + 8)? as usize;
+        let offset = u16_at(name, record + 10)? as usize;
+        let bytes = match storage.get(offset .. offset + length) {
+            Some(bytes) => bytes,
+            None => continue
+        };
+        let (rank, string) = match platform {
+            0 | 3 => {
+                let utf16: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+                let rank = if platform == 3 && language == 0x409 { 0 } else { 1 };
+                (rank, String::from_utf16_lossy(&utf16))
+            }
+            // mac roman, which matches latin-1 for the common characters
+            1 => (if language == 0 { 0 } else { 2 }, bytes.iter().map(|&b| b as char).collect()),
+            _ => continue
+        };
+        if best.as_ref().map_or(true, |&(r, _)| rank < r) {
+            best = Some((rank, string));
+        }
+    }
+    best.map(|(_, s)| s).filter(|s| s.len() > 0)
+}
+
+struct Face {
+    font: Font,
+    description: FaceDescription,
+}
+
+/// a set of faces to select fonts from
+pub struct FontDatabase {
+    faces: Vec<Face>,
+    generic: HashMap<Generic, Vec<String>>,
+}
+impl FontDatabase {
+    pub fn new() -> FontDatabase {
+        FontDatabase {
+            faces: vec![],
+            generic: HashMap::new(),
+        }
+    }
+
+    /// add a font, described by its own tables
+    pub fn add_font(&mut self, font: Font) {
+        let description = font.description().clone();
+        self.add_face(font, description);
+    }
+
+    /// add a font with the given description, like a `@font-face` rule does
+    pub fn add_face(&mut self, font: Font, description: FaceDescription) {
+        self.faces.push(Face { font, description });
+    }
+
+    /// set the families a generic family refers to, in order of preference
+    pub fn set_generic(&mut self, generic: Generic, families: &[&str]) {
+        self.generic.insert(generic, families.iter().map(|&s| s.into()).collect());
+    }
+
+    /// the best matching face of each of the families that are available
+    pub fn select(&self, families: &[FamilyName], weight: u16, slant: Slant, stretch: f32) -> Vec<Font> {
+        let mut fonts: Vec<Font> = vec![];
+        let mut add = |family: &str| {
+            if let Some(face) = self.match_family(family, weight, slant, stretch) {
+                fonts.push(face.font.clone());
+            }
+        };
+        for family in families {
+            match *family {
+                FamilyName::Named(name) => add(name),
+                FamilyName::Generic(generic) => {
+                    for name in self.generic.get(&generic).into_iter().flatten() {
+                        add(name.as_str());
+                    }
+                }
+            }
+        }
+        fonts
+    }
+
+    fn match_family(&self, family: &str, weight: u16, slant: Slant, stretch: f32) -> Option<&Face> {
+        let faces: Vec<&Face> = self.faces.iter()
+            .filter(|face| face.description.families.iter().any(|f| f.eq_ignore_ascii_case(family)))
+            .collect();
+
+        // narrower widths are preferred for condensed queries, wider ones otherwise
+        let stretch = closest(faces.iter().map(|f| f.description.stretch), stretch, stretch <= 100.)?;
+        let faces: Vec<&Face> = faces.into_iter().filter(|f| f.description.stretch == stretch).collect();
+
+        let slant_order: &[Slant] = match slant {
+            Slant::Normal => &[Slant::Normal, Slant::Oblique, Slant::Italic],
+            Slant::Italic => &[Slant::Italic, Slant::Oblique, Slant::Normal],
+            Slant::Oblique => &[Slant::Oblique, Slant::Italic, Slant::Normal],
+        };
+        let slant = slant_order.iter().find(|&&s| faces.iter().any(|f| f.description.slant == s))?;
+        let faces: Vec<&Face> = faces.into_iter().filter(|f| f.description.slant == *slant).collect();
+
+        let weights = faces.iter().map(|f| f.description.weight as f32);
+        let desired = weight as f32;
+        // between 400 and 500 the weights up to 500 are tried first
+        let window = if weight >= 400 && weight <= 500 {
+            weights.clone().filter(|&w| w >= desired && w <= 500.).fold(None, |m: Option<f32>, w| Some(m.map_or(w, |m| m.min(w))))
+        } else {
+            None
+        };
+        let weight = window.or_else(|| closest(weights, desired, weight <= 500))?;
+        faces.into_iter().find(|f| f.description.weight as f32 == weight)
+    }
+}
+
+/// the closest value in the preferred direction, or the closest one in the other direction
+fn closest(values: impl Iterator<Item=f32> + Clone, desired: f32, prefer_lower: bool) -> Option<f32> {
+    let below = values.clone().filter(|&v| v <= desired).fold(None, |m: Option<f32>, v| Some(m.map_or(v, |m| m.max(v))));
+    let above = values.filter(|&v| v >= desired).fold(None, |m: Option<f32>, v| Some(m.map_or(v, |m| m.min(v))));
+    if prefer_lower {
+        below.or(above)
+    } else {
+        above.or(below)
+    }
+}
+
+#[test]
+fn test_closest() {
+    let values = [50., 100., 150.];
+    assert_eq!(closest(values.iter().cloned(), 120., true), Some(100.));
+    assert_eq!(closest(values.iter().cloned(), 120., false), Some(150.));
+    assert_eq!(closest(values.iter().cloned(), 40., true), Some(50.));
+    assert_eq!(closest(values.iter().cloned(), 200., false), Some(150.));
+    assert_eq!(closest(values.iter().cloned(), 100., false), Some(100.));
+    assert_eq!(closest([].iter().cloned(), 100., true), None);
+}
+
+#[test]
+fn test_match_family() {
+    let font = Font::load(include_bytes!("../../resources/latinmodern-math.otf"));
+    let mut db = FontDatabase::new();
+    let faces = [
+        (300, 100., Slant::Normal),
+        (400, 100., Slant::Normal),
+        (600, 100., Slant::Normal),
+        (400, 100., Slant::Oblique),
+        (700, 100., Slant::Italic),
+        (400, 75., Slant::Normal),
+        (400, 125., Slant::Normal),
+    ];
+    for &(weight, stretch, slant) in &faces {
+        db.add_face(font.clone(), FaceDescription { families: vec!["Test".into()], weight, stretch, slant });
+    }
+    let matched = |weight, slant, stretch| db.match_family("test", weight, slant, stretch)
+        .map(|face| (face.description.weight, face.description.stretch, face.description.slant));
+
+    assert_eq!(matched(400, Slant::Normal, 100.), Some((400, 100., Slant::Normal)));
+    // between 400 and 500, lighter weights come after the weights up to 500
+    assert_eq!(matched(450, Slant::Normal, 100.), Some((400, 100., Slant::Normal)));
+    assert_eq!(matched(350, Slant::Normal, 100.), Some((300, 100., Slant::Normal)));
+    assert_eq!(matched(500, Slant::Normal, 100.), Some((400, 100., Slant::Normal)));
+    assert_eq!(matched(550, Slant::Normal, 100.), Some((600, 100., Slant::Normal)));
+    assert_eq!(matched(900, Slant::Normal, 100.), Some((600, 100., Slant::Normal)));
+
+    // narrower widths first for condensed queries, wider ones otherwise
+    assert_eq!(matched(400, Slant::Normal, 90.), Some((400, 75., Slant::Normal)));
+    assert_eq!(matched(400, Slant::Normal, 110.), Some((400, 125., Slant::Normal)));
+    assert_eq!(matched(400, Slant::Normal, 60.), Some((400, 75., Slant::Normal)));
+
+    // italic falls back to oblique, then to normal
+    assert_eq!(matched(700, Slant::Italic, 100.), Some((700, 100., Slant::Italic)));
+    assert_eq!(matched(400, Slant::Italic, 75.), Some((400, 75., Slant::Normal)));
+    db.add_face(font.clone(), FaceDescription { families: vec!["Upright".into()], weight: 400, stretch: 100., slant: Slant::Normal });
+    db.add_face(font.clone(), FaceDescription { families: vec!["Upright".into()], weight: 400, stretch: 100., slant: Slant::Oblique });
+    assert_eq!(db.match_family("upright", 400, Slant::Italic, 100.).map(|face| face.description.slant), Some(Slant::Oblique));
+
+    assert!(db.match_family("missing", 400, Slant::Normal, 100.).is_none());
+}
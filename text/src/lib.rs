@@ -11,6 +11,9 @@ use font::{Glyph, GlyphId,
 use font::SvgGlyph;
 
 pub use font::FontError;
+pub use metrics::{FontMetrics, BaseTable, VerticalMetrics};
+pub use database::{FontDatabase, FaceDescription, FamilyName, Generic, Slant};
+pub use svg_font::{SvgFont, SvgFontData, SvgGlyphData, SvgKernPair, ArabicForm};
 use pathfinder_geometry::{
     vector::{Vector2F, vec2f},
     transform2d::Transform2F,
@@ -26,16 +29,45 @@ use unicode_joining_type::{get_joining_type, JoiningType};
 use isolang::Language;
 
 #[derive(Clone)]
-pub struct Font(Arc<dyn font::Font + Sync + Send>);
+pub struct Font {
+    font: Arc<dyn font::Font + Sync + Send>,
+    metrics: Arc<FontMetrics>,
+    description: Arc<FaceDescription>,
+}
 impl Font {
     pub fn load(data: &[u8]) -> Font {
-        Font(Arc::from(font::parse(data).unwrap()))
+        Font::parse(data).unwrap()
+    }
+    /// like `load`, but fails on unsupported or broken data.
+    /// WOFF files are unpacked first, WOFF2 files are left to the font crate and have no metrics.
+    pub fn parse(data: &[u8]) -> Result<Font, FontError> {
+        let unpacked = woff::unpack(data);
+        let data = unpacked.as_ref().map(|v| v.as_slice()).unwrap_or(data);
+        Ok(Font {
+            font: Arc::from(font::parse(data)?),
+            metrics: Arc::new(FontMetrics::parse(data)),
+            description: Arc::new(FaceDescription::parse(data)),
+        })
+    }
+    /// a font from an SVG `<font>` element
+    pub fn from_svg(data: &SvgFontData) -> Font {
+        Font {
+            font: Arc::new(SvgFont::new(data)),
+            metrics: Arc::new(SvgFont::metrics(data)),
+            description: Arc::new(SvgFont::description(data)),
+        }
+    }
+    pub fn metrics(&self) -> &FontMetrics {
+        &self.metrics
+    }
+    pub fn description(&self) -> &FaceDescription {
+        &self.description
     }
 }
 impl std::ops::Deref for Font {
     type Target = dyn font::Font + Sync + Send;
     fn deref(&self) -> &(dyn font::Font + Sync + Send) {
-        &*self.0
+        &*self.font
     }
 }
 impl Debug for Font {
@@ -106,6 +138,14 @@ enum GlyphLocation {
     Isolated,
 }
 impl GlyphLocation {
+    fn arabic_form(self) -> ArabicForm {
+        match self {
+            GlyphLocation::Initial => ArabicForm::Initial,
+            GlyphLocation::Middle => ArabicForm::Medial,
+            GlyphLocation::Final => ArabicForm::Terminal,
+            GlyphLocation::Isolated => ArabicForm::Isolated,
+        }
+    }
     fn join(self, next: GlyphLocation) -> GlyphLocation {
         match (self, next) {
             (GlyphLocation::Initial, GlyphLocation::Final) => GlyphLocation::Isolated,
@@ -124,15 +164,91 @@ struct MetaGlyph {
     location: GlyphLocation,
     category: GeneralCategory,
     idx: usize,
+    // last codepoint of a grapheme cluster
+    cluster_end: bool,
 }
 impl MetaGlyph {
-    fn new(codepoint: char, idx: usize) -> MetaGlyph {
+    fn new(codepoint: char, idx: usize, cluster_end: bool) -> MetaGlyph {
         MetaGlyph {
             codepoint,
             joining_type: get_joining_type(codepoint),
             location: GlyphLocation::Isolated,
             category: GeneralCategory::of(codepoint),
-            idx
+            idx,
+            cluster_end,
+        }
+    }
+    /// whether the glyph stays upright in vertical text
+    fn is_upright(&self, orientation: Orientation) -> bool {
+        match orientation {
+            Orientation::Upright => true,
+            Orientation::Sideways => false,
+            Orientation::Mixed => is_upright_char(self.codepoint),
+        }
+    }
+    /// characters that receive the word spacing
+    fn is_word_separator(&self) -> bool {
+        match self.codepoint {
+            ' ' | '\u{a0}' | '\u{1361}' | '\u{10100}' | '\u{10101}' | '\u{1039f}' | '\u{1091f}' => true,
+            _ => false
+        }
+    }
+}
+
+/// characters that are upright in vertical text with mixed orientation,
+/// roughly the `U` and `Tu` classes of UAX #50
+fn is_upright_char(c: char) -> bool {
+    match c as u32 {
+        0xA7 | 0xA9 | 0xAE | 0xB1 | 0xBC ..= 0xBE | 0xD7 | 0xF7 => true,
+        0x1100 ..= 0x11FF => true, // hangul jamo
+        0x2E80 ..= 0x31FF => true, // radicals, CJK punctuation, kana, bopomofo
+        0x3200 ..= 0x9FFF => true, // enclosed and compatibility CJK, ideographs
+        0xA000 ..= 0xA4CF => true, // yi
+        0xA960 ..= 0xA97F | 0xAC00 ..= 0xD7FF => true, // hangul
+        0xF900 ..= 0xFAFF => true, // compatibility ideographs
+        0xFE10 ..= 0xFE1F | 0xFE30 ..= 0xFE4F => true, // vertical and compatibility forms
+        0xFF01 ..= 0xFF60 | 0xFFE0 ..= 0xFFE7 => true, // fullwidth forms
+        0x1F000 ..= 0x1FAFF => true, // symbols and emoji
+        0x20000 ..= 0x3FFFF => true, // supplementary ideographs
+        _ => false
+    }
+}
+
+/// the features of the vertical alternates, which only apply to upright glyphs
+fn vertical_features(m: &MetaGlyph, orientation: Orientation) -> impl Fn(Tag) -> bool {
+    let upright = m.is_upright(orientation);
+    move |tag: Tag| upright && [Tag(*b"vert"), Tag(*b"vrt2")].contains(&tag)
+}
+
+/// orientation of the glyphs in vertical text
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Orientation {
+    /// CJK characters are upright, the others are rotated clockwise
+    Mixed,
+    Upright,
+    Sideways,
+}
+
+/// spacing and kerning applied by `layout_run`. spacings are in em.
+#[derive(Debug, Copy, Clone)]
+pub struct LayoutOptions {
+    /// added after each grapheme cluster
+    pub letter_spacing: f32,
+    /// added after each word separator
+    pub word_spacing: f32,
+    pub kerning: bool,
+    /// glyphs advance downwards, centered on the vertical baseline
+    pub vertical: bool,
+    pub orientation: Orientation,
+}
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        LayoutOptions {
+            letter_spacing: 0.0,
+            word_spacing: 0.0,
+            kerning: true,
+            vertical: false,
+            orientation: Orientation::Mixed,
         }
     }
 }
@@ -188,13 +304,15 @@ fn sub_pass<F, G>(gsub: &GSub, lang: &LanguageSystem, meta: &[MetaGlyph], gids:
     }
 }
 
-fn process_chunk(font: &Font, font_idx: usize, language: Option<Tag>, rtl: bool, meta: &[MetaGlyph], state: &mut State) {
-    if let Some(fm) = font.vmetrics() {
+fn process_chunk(font: &Font, font_idx: usize, language: Option<Tag>, rtl: bool, options: &LayoutOptions, meta: &[MetaGlyph], state: &mut State) {
+    let font_vmetrics = font.vmetrics().map(|fm| {
         let s = font.font_matrix().m22();
-        let vm = VMetrics {
+        VMetrics {
             ascent: fm.ascent * s,
             descent: fm.descent * s
-        };
+        }
+    });
+    if let Some(vm) = font_vmetrics {
         state.vmetrics = match state.vmetrics {
             None => Some(vm),
             Some(m1) => Some(VMetrics { ascent: m1.ascent.max(vm.ascent), descent: m1.descent.min(vm.descent) })
@@ -205,13 +323,16 @@ fn process_chunk(font: &Font, font_idx: usize, language: Option<Tag>, rtl: bool,
         debug!("at byte {} [\u{2068}{}\u{2069} 0x{:x}]", g.idx, g.codepoint, g.codepoint as u32);
     }
     // (codepoint idx, glyph id)
-    let mut gids: Vec<(usize, GlyphId)> = meta.iter()
+    let visible = meta.iter()
         .filter(|&m| match m.category {
             GeneralCategory::Format => false,
             _ => true
-        })
-        .map(|m| (m.idx, font.gid_for_unicode_codepoint(m.codepoint as u32).unwrap()))
-        .collect();
+        });
+    let mut gids: Vec<(usize, GlyphId)> = match font.downcast_ref::<SvgFont>() {
+        // SVG fonts select arabic forms and ligatures by the characters themselves
+        Some(svg) => svg.shape(&visible.map(|m| (m.idx, m.codepoint, m.location.arabic_form())).collect::<Vec<_>>()),
+        None => visible.map(|m| (m.idx, font.gid_for_unicode_codepoint(m.codepoint as u32).unwrap())).collect()
+    };
 
     let otf = font.downcast_ref::<OpenTypeFont>();
     let gsub = otf.and_then(|f| f.gsub.as_ref());
@@ -230,10 +351,14 @@ fn process_chunk(font: &Font, font_idx: usize, language: Option<Tag>, rtl: bool,
                 move |tag: Tag| tag == arabic_tag
             });
             sub_pass(gsub, lang, meta, &mut gids, |m| |tag| [Tag(*b"rlig"), Tag(*b"liga")].contains(&tag));
+            if options.vertical {
+                sub_pass(gsub, lang, meta, &mut gids, |m| vertical_features(m, options.orientation));
+            }
         }
     }
     
     let mut last_gid = None;
+    let mut base_offset = state.offset;
     for (index, gid) in gids {
         if let Some(glyph) = font.glyph(gid) {
             let mark = match (gdef.and_then(|gdef| gdef.mark_class(gid.0 as u16)).unwrap_or(MarkClass::Unassigned), last_gid) {
@@ -243,24 +368,71 @@ fn process_chunk(font: &Font, font_idx: usize, language: Option<Tag>, rtl: bool,
                 _ => None
             };
 
-            let (advance, offset) = match mark {
+            let transform = Transform2F::from_scale(vec2f(1.0, -1.0)) * font.font_matrix();
+            let (advance, offset, transform) = match mark {
+                None if options.vertical => {
+                    last_gid = Some(gid);
+                    let upright = meta.binary_search_by_key(&index, |m| m.idx).ok()
+                        .map_or(false, |pos| meta[pos].is_upright(options.orientation));
+                    let h_advance = (font.font_matrix() * vec2f(glyph.metrics.advance, 0.0)).x();
+                    let VMetrics { ascent, descent } = font_vmetrics.unwrap_or(VMetrics { ascent: 0.8, descent: -0.2 });
+                    if upright {
+                        // distance from the vertical origin down to the baseline of the glyph
+                        let (advance, origin) = match font.metrics().vertical.as_ref().and_then(|v| v.glyph(gid.0)) {
+                            Some((advance, tsb)) => (advance, tsb + (font.font_matrix() * glyph.path.bounds()).max_y()),
+                            None => (ascent - descent, ascent)
+                        };
+                        (vec2f(0.0, advance), state.offset + vec2f(-0.5 * h_advance, origin), transform)
+                    } else {
+                        // rotated clockwise, with the middle of ascent and descent on the vertical baseline
+                        let center = 0.5 * (ascent + descent);
+                        let rotation = Transform2F::from_rotation(0.5 * std::f32::consts::PI);
+                        (vec2f(0.0, h_advance), state.offset + vec2f(-center, 0.0), rotation * transform)
+                    }
+                }
                 None => {
-                    let kerning = font.font_matrix() * vec2f(last_gid.replace(gid).map(|left| font.kerning(left, gid)).unwrap_or_default(), 0.0);
+                    let kerning = match (last_gid.replace(gid), options.kerning) {
+                        (Some(left), true) => font.font_matrix() * vec2f(font.kerning(left, gid), 0.0),
+                        _ => Vector2F::zero()
+                    };
                     let advance = font.font_matrix() * vec2f(glyph.metrics.advance, 0.0) + kerning;
                     match rtl {
-                        false => (advance, state.offset + kerning),
-                        true => (advance * vec2f(-1.0, 1.0), state.offset - advance)
+                        false => (advance, state.offset + kerning, transform),
+                        true => (advance * vec2f(-1.0, 1.0), state.offset - advance, transform)
                     }
                 }
+                // vertical marks are positioned relative to their base glyph
+                Some((dx, dy)) if options.vertical => {
+                    let delta = font.font_matrix() * vec2f(dx as f32, dy as f32);
+                    (Vector2F::zero(), base_offset + delta, transform)
+                }
                 Some((dx, dy)) => {
                     let delta = font.font_matrix() * vec2f(dx as f32, dy as f32);
-                    (Vector2F::zero(), state.offset + delta)
+                    (Vector2F::zero(), state.offset + delta, transform)
                 }
             };
 
-            let transform = Transform2F::from_scale(vec2f(1.0, -1.0)) * font.font_matrix();
+            if mark.is_none() {
+                base_offset = offset;
+            }
+            let mut advance = advance;
+            if let Ok(pos) = meta.binary_search_by_key(&index, |m| m.idx) {
+                let m = &meta[pos];
+                let mut spacing = 0.0;
+                if m.cluster_end {
+                    spacing += options.letter_spacing;
+                }
+                if m.is_word_separator() {
+                    spacing += options.word_spacing;
+                }
+                advance = advance + match (options.vertical, rtl) {
+                    (true, _) => vec2f(0.0, spacing),
+                    (false, false) => vec2f(spacing, 0.0),
+                    (false, true) => vec2f(-spacing, 0.0),
+                };
+            }
             state.offset += advance;
-            state.glyphs.push(LayoutGlyph { gid, transform, offset, index, font_idx });
+            state.glyphs.push(LayoutGlyph { gid, transform, offset, index, font_idx, advance });
         }
     }
 }
@@ -275,6 +447,9 @@ pub struct LayoutGlyph {
     
     // index of the font in the fontcollection this glyph belongs to
     pub font_idx: usize,
+
+    // how far the pen moves, including letter and word spacing
+    pub advance: Vector2F,
 }
 
 #[derive(Copy, Clone)]
@@ -290,6 +465,23 @@ struct State {
     vmetrics: Option<VMetrics>,
 }
 
+/// the glyph information of each char of `word`, which starts at byte `word_off` of the input
+fn meta_glyphs(word: &str, word_off: usize) -> Vec<MetaGlyph> {
+    // byte index of the last char of each grapheme cluster, in order
+    let mut cluster_ends = GraphemeIndices::new(word)
+        .filter_map(|(idx, grapheme)| grapheme.char_indices().last().map(|(last, _)| idx + last))
+        .peekable();
+    word.char_indices()
+        .map(|(idx, c)| {
+            let cluster_end = cluster_ends.peek() == Some(&idx);
+            if cluster_end {
+                cluster_ends.next();
+            }
+            MetaGlyph::new(c, word_off + idx, cluster_end)
+        })
+        .collect()
+}
+
 fn font_for_text<'a>(fonts: &'a [Font], text: &str, meta: &[MetaGlyph]) -> Option<(usize, &'a Font)> {
     fonts.iter().enumerate()
         .filter(|(_, font)|
@@ -303,7 +495,7 @@ fn font_for_text<'a>(fonts: &'a [Font], text: &str, meta: &[MetaGlyph]) -> Optio
 }
 
 impl FontCollection {
-    pub fn layout_run(&self, string: &str, rtl: bool, lang: Option<Language>) -> Layout {
+    pub fn layout_run(&self, string: &str, rtl: bool, lang: Option<Language>, options: &LayoutOptions) -> Layout {
         let lang = lang.and_then(tags::lang_to_tag);
 
         #[cfg(feature="detect")]
@@ -323,12 +515,12 @@ impl FontCollection {
         // we process each word separately to improve the visual appearance by trying to render a word in a single font
         for (word_off, word) in WordBoundIndices::new(string) {
             // do stuff… borrowed from allsorts
-            let mut meta: Vec<MetaGlyph> = word.char_indices().map(|(idx, c)| MetaGlyph::new(c, word_off+idx)).collect();
+            let mut meta = meta_glyphs(word, word_off);
             compute_joining(&mut meta);
             
             // try to find a font that has all glyphs
             if let Some((font_idx, font)) = font_for_text(fonts, word, &meta) {
-                process_chunk(font, font_idx, lang, rtl, &meta, &mut state);
+                process_chunk(font, font_idx, lang, rtl, options, &meta, &mut state);
             } else {
                 let mut start = 0;
                 let mut meta_idx = 0;
@@ -339,7 +531,7 @@ impl FontCollection {
                     if let Some((font_idx, font)) = font_for_text(fonts, grapheme, &meta[meta_idx .. meta_idx + meta_len]) {
                         if Some(font_idx) != current_font.map(|(i, _)| i) && idx > 0 {
                             // flush so fart.0
-                            process_chunk(font, font_idx, lang, rtl, &meta[start .. idx], &mut state);
+                            process_chunk(font, font_idx, lang, rtl, options, &meta[start .. idx], &mut state);
                             start = idx;
                         }
                         current_font = Some((font_idx, font));
@@ -350,7 +542,7 @@ impl FontCollection {
                     meta_idx += meta_len;
                 }
                 if let Some((font_idx, font)) = current_font {
-                    process_chunk(font, font_idx, lang, rtl, &meta[meta_idx ..], &mut state);
+                    process_chunk(font, font_idx, lang, rtl, options, &meta[meta_idx ..], &mut state);
                 }
             }
         }
@@ -386,9 +578,80 @@ impl FontCollection {
             })
             .fold1(|a, b| a.union_rect(b)).unwrap_or_default()
     }
+
+    /// height of a lowercase 'x' in em, taken from the first font that has one
+    pub fn x_height(&self) -> Option<f32> {
+        self.fonts.iter().find_map(|font| {
+            if let Some(x_height) = font.metrics().x_height {
+                return Some(x_height);
+            }
+            let gid = font.gid_for_unicode_codepoint('x' as u32)?;
+            let glyph = font.glyph(gid)?;
+            Some(font.font_matrix().m22() * glyph.path.bounds().max_y())
+        })
+    }
+
+    /// baselines of the primary font. the BASE table is used where present,
+    /// the other baselines are derived from the ascent, descent and x-height.
+    pub fn baselines(&self) -> Baselines {
+        let font = self.fonts.first();
+        let base = font.map(|f| f.metrics().base.clone()).unwrap_or_default();
+        let (ascent, descent) = font.and_then(|f| {
+            let s = f.font_matrix().m22();
+            f.vmetrics().map(|m| (s * m.ascent, s * m.descent))
+        }).unwrap_or((0.8, -0.2));
+        let x_height = self.x_height().unwrap_or(0.5);
+
+        // BASE coordinates are relative to the baseline of the glyph outlines, which is usually the alphabetic one
+        let alphabetic = base.alphabetic.unwrap_or(0.0);
+        let ideographic = base.ideographic.map(|v| v - alphabetic).unwrap_or(descent);
+        let central = match (base.ideographic, base.ideographic_top) {
+            (Some(bottom), Some(top)) => 0.5 * (bottom + top) - alphabetic,
+            _ => 0.5 * (ascent + descent)
+        };
+        Baselines {
+            alphabetic: 0.0,
+            ideographic,
+            hanging: base.hanging.map(|v| v - alphabetic).unwrap_or(0.8 * ascent),
+            mathematical: base.mathematical.map(|v| v - alphabetic).unwrap_or(0.5 * ascent),
+            central,
+            middle: 0.5 * x_height,
+            text_top: ascent,
+            text_bottom: descent,
+        }
+    }
+
+    /// offsets of the (subscript, superscript) baselines in em, positive is up
+    pub fn script_offsets(&self) -> (f32, f32) {
+        let metrics = self.fonts.first().map(|f| f.metrics());
+        (
+            -metrics.and_then(|m| m.subscript_offset).unwrap_or(0.2),
+            metrics.and_then(|m| m.superscript_offset).unwrap_or(0.4),
+        )
+    }
+
+    /// lines drawn by `text-decoration`
+    pub fn decorations(&self) -> Decorations {
+        let metrics = self.fonts.first().map(|f| f.metrics());
+        let baselines = self.baselines();
+        let underline_thickness = metrics.and_then(|m| m.underline_thickness).unwrap_or(0.05);
+        let strikeout_thickness = metrics.and_then(|m| m.strikeout_thickness).unwrap_or(underline_thickness);
+        let underline_top = metrics.and_then(|m| m.underline_position).unwrap_or(-0.1);
+        let strikeout_top = metrics.and_then(|m| m.strikeout_position)
+            .unwrap_or(baselines.middle + 0.5 * strikeout_thickness);
+        Decorations {
+            underline: DecorationLine { position: underline_top - 0.5 * underline_thickness, thickness: underline_thickness },
+            overline: DecorationLine { position: baselines.text_top - 0.5 * underline_thickness, thickness: underline_thickness },
+            line_through: DecorationLine { position: strikeout_top - 0.5 * strikeout_thickness, thickness: strikeout_thickness },
+        }
+    }
 }
 
 mod tags;
+mod metrics;
+mod database;
+mod woff;
+mod svg_font;
 
 #[cfg(feature="detect")]
 fn guess_lang(text: &str) -> Option<Tag> {
@@ -417,12 +680,79 @@ pub struct TextMetrics {
     pub descent: f32,
 }
 
-enum HAlign {
-    Left,
-    Center,
-    Right
+/// positions of the baselines in em, relative to the alphabetic baseline (positive is up)
+#[derive(Debug, Copy, Clone)]
+pub struct Baselines {
+    pub alphabetic: f32,
+    pub ideographic: f32,
+    pub hanging: f32,
+    pub mathematical: f32,
+    pub central: f32,
+    pub middle: f32,
+    pub text_top: f32,
+    pub text_bottom: f32,
+}
+
+/// a decoration line in em. the position is the center of the line relative to the alphabetic baseline (positive is up).
+#[derive(Debug, Copy, Clone)]
+pub struct DecorationLine {
+    pub position: f32,
+    pub thickness: f32,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Decorations {
+    pub underline: DecorationLine,
+    pub overline: DecorationLine,
+    pub line_through: DecorationLine,
+}
+
+#[cfg(test)]
+fn test_fonts() -> FontCollection {
+    FontCollection::from_font(Font::load(include_bytes!("../../resources/latinmodern-math.otf")))
+}
+
+#[test]
+fn test_cluster_ends() {
+    let ends: Vec<bool> = meta_glyphs("e\u{301}x", 3).iter().map(|m| m.cluster_end).collect();
+    assert_eq!(ends, [false, true, true]);
+    assert_eq!(meta_glyphs("e\u{301}x", 3)[2].idx, 6);
+}
+
+#[test]
+fn test_spacing() {
+    let fonts = test_fonts();
+    let advance = |letter_spacing, word_spacing| {
+        let options = LayoutOptions { letter_spacing, word_spacing, kerning: false, .. LayoutOptions::default() };
+        fonts.layout_run("ab cd", false, None, &options).metrics.advance.x()
+    };
+    let normal = advance(0.0, 0.0);
+    // after each of the five clusters
+    assert!((advance(0.1, 0.0) - normal - 0.5).abs() < 1e-4);
+    // after the space only
+    assert!((advance(0.0, 0.3) - normal - 0.3).abs() < 1e-4);
 }
 
-enum VAlign {
-    Baseline
-}
\ No newline at end of file
+#[test]
+fn test_upright_chars() {
+    for &c in &['中', 'あ', 'ア', '한', '、', '\u{FF01}', '\u{1F600}', '\u{20000}', '\u{A9}'] {
+        assert!(is_upright_char(c), "{:?}", c);
+    }
+    for &c in &['a', '1', '-', 'α', 'ب', '\u{2014}'] {
+        assert!(!is_upright_char(c), "{:?}", c);
+    }
+}
+
+#[test]
+fn test_vertical_features() {
+    let meta = meta_glyphs("中a", 0);
+    let vert = |m: &MetaGlyph, orientation| (vertical_features(m, orientation)(Tag(*b"vert")), vertical_features(m, orientation)(Tag(*b"vrt2")));
+    assert_eq!(vert(&meta[0], Orientation::Mixed), (true, true));
+    assert_eq!(vert(&meta[1], Orientation::Mixed), (false, false));
+    assert_eq!(vert(&meta[0], Orientation::Upright), (true, true));
+    assert_eq!(vert(&meta[1], Orientation::Upright), (true, true));
+    assert_eq!(vert(&meta[0], Orientation::Sideways), (false, false));
+    assert_eq!(vert(&meta[1], Orientation::Sideways), (false, false));
+    // other features are not affected
+    assert!(!vertical_features(&meta[0], Orientation::Upright)(Tag(*b"liga")));
+}
@@ -0,0 +1,218 @@
+//! font wide metrics that are read directly from the sfnt tables
+
+/// metrics of a font in em. `None` if the font doesn't provide the value.
+#[derive(Debug, Clone, Default)]
+pub struct FontMetrics {
+    pub x_height: Option<f32>,
+    pub cap_height: Option<f32>,
+
+    /// distance of the subscript baseline below the alphabetic baseline
+    pub subscript_offset: Option<f32>,
+    /// distance of the superscript baseline above the alphabetic baseline
+    pub superscript_offset: Option<f32>,
+
+    /// top of the underline from the `post` table, relative to the alphabetic baseline
+    pub underline_position: Option<f32>,
+    pub underline_thickness: Option<f32>,
+    /// top of the strikeout stroke from the OS/2 table
+    pub strikeout_position: Option<f32>,
+    pub strikeout_thickness: Option<f32>,
+
+    /// horizontal baselines from the BASE table, relative to the alphabetic baseline
+    pub base: BaseTable,
+
+    /// from the vhea and vmtx tables
+    pub vertical: Option<VerticalMetrics>,
+}
+
+/// advance height and top side bearing of each glyph, in em
+#[derive(Debug, Clone)]
+pub struct VerticalMetrics {
+    glyphs: Vec<(f32, f32)>,
+}
+impl VerticalMetrics {
+    /// (advance height, top side bearing) of the glyph
+    pub fn glyph(&self, gid: u32) -> Option<(f32, f32)> {
+        self.glyphs.get(gid as usize).cloned()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BaseTable {
+    pub alphabetic: Option<f32>,
+    pub ideographic: Option<f32>,
+    pub ideographic_top: Option<f32>,
+    pub hanging: Option<f32>,
+    pub mathematical: Option<f32>,
+}
+
+pub(crate) fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset .. offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+pub(crate) fn i16_at(data: &[u8], offset: usize) -> Option<i16> {
+    u16_at(data, offset).map(|v| v as i16)
+}
+pub(crate) fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset .. offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+pub(crate) fn tag_at(data: &[u8], offset: usize) -> Option<&[u8]> {
+    data.get(offset .. offset + 4)
+}
+/// the data at the 16 bit offset stored at `pos`, `None` for null offsets
+fn offset16(data: &[u8], pos: usize) -> Option<&[u8]> {
+    match u16_at(data, pos)? {
+        0 => None,
+        offset => data.get(offset as usize ..)
+    }
+}
+
+/// find a table in the sfnt table directory
+pub(crate) fn table<'a>(data: &'a [u8], tag: &[u8; 4]) -> Option<&'a [u8]> {
+    let num_tables = u16_at(data, 4)? as usize;
+    (0 .. num_tables).map(|i| 12 + 16 * i).find_map(|record| {
+        if tag_at(data, record)? != tag {
+            return None;
+        }
+        let offset = u32_at(data, record + 8)? as usize;
+        let length = u32_at(data, record + 12)? as usize;
+        data.get(offset .. offset + length)
+    })
+}
+
+impl FontMetrics {
+    /// fonts in other formats (like Type1 or collections) have no metrics
+    pub fn parse(data: &[u8]) -> FontMetrics {
+        let units_per_em = match table(data, b"head").and_then(|head| u16_at(head, 18)) {
+            Some(units) if units > 0 => units as f32,
+            _ => return FontMetrics::default()
+        };
+        let em = |v: i16| v as f32 / units_per_em;
+
+        let mut metrics = FontMetrics::default();
+        if let Some(os2) = table(data, b"OS/2") {
+            metrics.subscript_offset = i16_at(os2, 16).map(em);
+            metrics.superscript_offset = i16_at(os2, 24).map(em);
+            metrics.strikeout_thickness = i16_at(os2, 26).filter(|&v| v > 0).map(em);
+            metrics.strikeout_position = i16_at(os2, 28).map(em);
+            // sxHeight and sCapHeight were added in version 2
+            if u16_at(os2, 0).map_or(false, |version| version >= 2) {
+                metrics.x_height = i16_at(os2, 86).filter(|&v| v > 0).map(em);
+                metrics.cap_height = i16_at(os2, 88).filter(|&v| v > 0).map(em);
+            }
+        }
+        if let Some(post) = table(data, b"post") {
+            metrics.underline_position = i16_at(post, 8).map(em);
+            metrics.underline_thickness = i16_at(post, 10).filter(|&v| v > 0).map(em);
+        }
+        if let Some(base) = table(data, b"BASE") {
+            metrics.base = parse_base(base, em).unwrap_or_default();
+        }
+        metrics.vertical = parse_vertical(data, units_per_em);
+        metrics
+    }
+}
+
+fn parse_vertical(data: &[u8], units_per_em: f32) -> Option<VerticalMetrics> {
+    let vhea = table(data, b"vhea")?;
+    let vmtx = table(data, b"vmtx")?;
+    let num_long = u16_at(vhea, 34)? as usize;
+    if num_long == 0 {
+        return None;
+    }
+    let num_glyphs = table(data, b"maxp").and_then(|maxp| u16_at(maxp, 4))
+        .map_or(num_long, |n| (n as usize).max(num_long));
+
+    // glyphs after the long metrics only have a top side bearing and use the last advance
+    let mut glyphs = Vec::with_capacity(num_glyphs);
+    let mut advance = 0;
+    for gid in 0 .. num_glyphs {
+        let tsb = if gid < num_long {
+            advance = u16_at(vmtx, 4 * gid)?;
+            i16_at(vmtx, 4 * gid + 2)?
+        } else {
+            i16_at(vmtx, 4 * num_long + 2 * (gid - num_long)).unwrap_or(0)
+        };
+        glyphs.push((advance as f32 / units_per_em, tsb as f32 / units_per_em));
+    }
+    Some(VerticalMetrics { glyphs })
+}
+
+/// baselines of the horizontal axis for the latin script, or the default script
+fn parse_base(base: &[u8], em: impl Fn(i16) -> f32) -> Option<BaseTable> {
+    let axis = offset16(base, 4)?;
+    let tag_list = offset16(axis, 0)?;
+    let script_list = offset16(axis, 2)?;
+
+    // BaseScriptRecords are a tag followed by the offset of the script
+    let num_scripts = u16_at(script_list, 0)? as usize;
+    let script_record = |tag: &[u8]| (0 .. num_scripts).map(|i| 2 + 6 * i)
+        .find(|&record| tag_at(script_list, record) == Some(tag));
+    let record = script_record(&b"latn"[..])
+        .or_else(|| script_record(&b"DFLT"[..]))
+        .or(Some(2).filter(|_| num_scripts > 0))?;
+    let script = offset16(script_list, record + 4)?;
+    let values = offset16(script, 0)?;
+
+    let num_tags = u16_at(tag_list, 0)? as usize;
+    let num_coords = u16_at(values, 2)? as usize;
+    let mut table = BaseTable::default();
+    for i in 0 .. num_tags.min(num_coords) {
+        let coord = offset16(values, 4 + 2 * i)?;
+        // all formats start with the format and the coordinate
+        let value = Some(em(i16_at(coord, 2)?));
+        match tag_at(tag_list, 2 + 4 * i)? {
+            b"romn" => table.alphabetic = value,
+            b"ideo" => table.ideographic = value,
+            b"idtp" => table.ideographic_top = value,
+            b"hang" => table.hanging = value,
+            b"math" => table.mathematical = value,
+            _ => {}
+        }
+    }
+    Some(table)
+}
+
+/// an sfnt file with the given tables
+#[cfg(test)]
+fn test_sfnt(tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let mut data = vec![0, 1, 0, 0];
+    data.extend_from_slice(&(tables.len() as u16).to_be_bytes());
+    data.extend_from_slice(&[0; 6]);
+    let mut offset = 12 + 16 * tables.len();
+    for &(tag, ref table) in tables {
+        data.extend_from_slice(tag);
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&(offset as u32).to_be_bytes());
+        data.extend_from_slice(&(table.len() as u32).to_be_bytes());
+        offset += table.len();
+    }
+    for (_, table) in tables {
+        data.extend_from_slice(table);
+    }
+    data
+}
+
+#[test]
+fn test_parse_vertical() {
+    let mut vhea = vec![0; 36];
+    vhea[34 .. 36].copy_from_slice(&2u16.to_be_bytes());
+    let vmtx: Vec<u8> = [1000u16, 100, 900, 50, 20].iter().flat_map(|v| v.to_be_bytes().to_vec()).collect();
+    let maxp = vec![0, 0, 0x50, 0, 0, 3];
+
+    // the third glyph only has a top side bearing and uses the last advance
+    let data = test_sfnt(&[(b"maxp", maxp), (b"vhea", vhea.clone()), (b"vmtx", vmtx.clone())]);
+    let vertical = parse_vertical(&data, 1000.).unwrap();
+    assert_eq!(vertical.glyph(0), Some((1.0, 0.1)));
+    assert_eq!(vertical.glyph(1), Some((0.9, 0.05)));
+    assert_eq!(vertical.glyph(2), Some((0.9, 0.02)));
+    assert_eq!(vertical.glyph(3), None);
+
+    // without maxp only the long metrics are known
+    let data = test_sfnt(&[(b"vhea", vhea.clone()), (b"vmtx", vmtx.clone())]);
+    assert_eq!(parse_vertical(&data, 1000.).unwrap().glyph(2), None);
+
+    // without vmtx there are no vertical metrics
+    assert!(parse_vertical(&test_sfnt(&[(b"vhea", vhea)]), 1000.).is_none());
+}
@@ -0,0 +1,315 @@
+//! fonts defined with the SVG 1.1 `<font>` element
+
+use font::{Glyph, GlyphId, HMetrics, VMetrics, Name};
+use pathfinder_content::outline::Outline;
+use pathfinder_geometry::{transform2d::Transform2F, vector::vec2f};
+use std::collections::HashMap;
+use crate::{FontMetrics, FaceDescription, Slant};
+
+/// the content of an SVG `<font>` and its `<font-face>`. lengths are in font units.
+#[derive(Debug, Clone)]
+pub struct SvgFontData {
+    pub family: Option<String>,
+    /// 1 to 1000, 400 is normal
+    pub weight: u16,
+    pub slant: Slant,
+    pub units_per_em: f32,
+    /// `None` for 0.8 em
+    pub ascent: Option<f32>,
+    /// distance below the baseline, `None` for 0.2 em
+    pub descent: Option<f32>,
+    pub x_height: Option<f32>,
+    pub cap_height: Option<f32>,
+    pub underline_position: Option<f32>,
+    pub underline_thickness: Option<f32>,
+    pub strikeout_position: Option<f32>,
+    pub strikeout_thickness: Option<f32>,
+    /// advance of the glyphs that don't have their own
+    pub advance: f32,
+    pub missing_glyph: Option<SvgGlyphData>,
+    /// in document order, which decides between overlapping matches
+    pub glyphs: Vec<SvgGlyphData>,
+    pub kerning: Vec<SvgKernPair>,
+}
+
+/// a `<glyph>` or `<missing-glyph>`
+#[derive(Debug, Clone)]
+pub struct SvgGlyphData {
+    /// the characters the glyph stands for, more than one for ligatures
+    pub unicode: Vec<char>,
+    pub names: Vec<String>,
+    pub outline: Outline,
+    /// `None` to use the advance of the font
+    pub advance: Option<f32>,
+    /// `None` if the glyph can be used for all forms
+    pub arabic_form: Option<ArabicForm>,
+}
+
+/// an `<hkern>`, a pair of glyphs that are moved closer by `k` font units.
+/// each side is given by inclusive character ranges or glyph names.
+#[derive(Debug, Clone)]
+pub struct SvgKernPair {
+    pub u1: Vec<(char, char)>,
+    pub g1: Vec<String>,
+    pub u2: Vec<(char, char)>,
+    pub g2: Vec<String>,
+    pub k: f32,
+}
+
+/// the form of an arabic glyph, depending on how it joins its neighbours
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ArabicForm {
+    Initial,
+    Medial,
+    Terminal,
+    Isolated,
+}
+
+#[derive(Debug)]
+struct SvgFontGlyph {
+    /// empty for glyphs that can't be selected by text
+    unicode: Vec<char>,
+    outline: Outline,
+    advance: f32,
+    arabic_form: Option<ArabicForm>,
+}
+
+/// a font from an SVG `<font>`. glyph 0 is the missing glyph.
+#[derive(Debug)]
+pub struct SvgFont {
+    glyphs: Vec<SvgFontGlyph>,
+    /// glyphs by their first character, in document order
+    by_char: HashMap<char, Vec<u32>>,
+    by_name: HashMap<String, u32>,
+    kerning: HashMap<(u32, u32), f32>,
+    units_per_em: f32,
+    ascent: f32,
+    descent: f32,
+    name: Name,
+}
+impl SvgFont {
+    pub fn new(data: &SvgFontData) -> SvgFont {
+        let glyph = |g: &SvgGlyphData| SvgFontGlyph {
+            unicode: g.unicode.clone(),
+            outline: g.outline.clone(),
+            advance: g.advance.unwrap_or(data.advance),
+            arabic_form: g.arabic_form,
+        };
+        let missing = match data.missing_glyph {
+            Some(ref g) => SvgFontGlyph { unicode: vec![], .. glyph(g) },
+            None => SvgFontGlyph { unicode: vec![], outline: Outline::new(), advance: data.advance, arabic_form: None },
+        };
+        let glyphs: Vec<SvgFontGlyph> = std::iter::once(missing).chain(data.glyphs.iter().map(glyph)).collect();
+
+        let mut by_char: HashMap<char, Vec<u32>> = HashMap::new();
+        let mut by_name = HashMap::new();
+        for (gid, (glyph, g)) in glyphs[1 ..].iter().zip(data.glyphs.iter()).enumerate() {
+            let gid = gid as u32 + 1;
+            if let Some(&first) = glyph.unicode.first() {
+                by_char.entry(first).or_default().push(gid);
+            }
+            for name in &g.names {
+                by_name.entry(name.clone()).or_insert(gid);
+            }
+        }
+
+        // the pairs are expanded to glyph ids, the first matching <hkern> wins
+        let mut kerning = HashMap::new();
+        for hkern in &data.kerning {
+            let matches = |unicode: &[(char, char)], names: &[String]| -> Vec<u32> {
+                data.glyphs.iter().enumerate().filter(|&(_, g)| {
+                    let single = match g.unicode[..] {
+                        [c] => Some(c),
+                        _ => None
+                    };
+                    single.map_or(false, |c| unicode.iter().any(|&(first, last)| c >= first && c <= last))
+                    || g.names.iter().any(|n| names.contains(n))
+                }).map(|(i, _)| i as u32 + 1).collect()
+            };
+            let right = matches(&hkern.u2, &hkern.g2);
+            for left in matches(&hkern.u1, &hkern.g1) {
+                for &right in &right {
+                    kerning.entry((left, right)).or_insert(hkern.k);
+                }
+            }
+        }
+
+        SvgFont {
+            glyphs,
+            by_char,
+            by_name,
+            kerning,
+            units_per_em: data.units_per_em,
+            ascent: data.ascent.unwrap_or(0.8 * data.units_per_em),
+            // the descent is given as a distance below the baseline
+            descent: -data.descent.map(f32::abs).unwrap_or(0.2 * data.units_per_em),
+            name: Name {
+                family: data.family.clone(),
+                full_name: data.family.clone(),
+                .. Name::default()
+            },
+        }
+    }
+
+    /// metrics in em from the `<font-face>`
+    pub(crate) fn metrics(data: &SvgFontData) -> FontMetrics {
+        let em = |v: Option<f32>| v.map(|v| v / data.units_per_em);
+        FontMetrics {
+            x_height: em(data.x_height),
+            cap_height: em(data.cap_height),
+            underline_position: em(data.underline_position),
+            underline_thickness: em(data.underline_thickness),
+            strikeout_position: em(data.strikeout_position),
+            strikeout_thickness: em(data.strikeout_thickness),
+            .. FontMetrics::default()
+        }
+    }
+
+    pub(crate) fn description(data: &SvgFontData) -> FaceDescription {
+        FaceDescription {
+            families: data.family.iter().cloned().collect(),
+            weight: data.weight,
+            slant: data.slant,
+            .. FaceDescription::default()
+        }
+    }
+
+    /// glyphs for the characters, each with its byte index and arabic form.
+    /// the first glyph in document order that matches the following characters is used,
+    /// so a ligature has to come before the glyphs of its parts.
+    pub(crate) fn shape(&self, chars: &[(usize, char, ArabicForm)]) -> Vec<(usize, GlyphId)> {
+        let mut gids = Vec::with_capacity(chars.len());
+        let mut pos = 0;
+        while let Some(&(idx, c, form)) = chars.get(pos) {
+            let found = self.by_char.get(&c).into_iter().flatten().find(|&&gid| {
+                let glyph = &self.glyphs[gid as usize];
+                glyph.arabic_form.map_or(true, |f| f == form)
+                    && glyph.unicode.len() <= chars.len() - pos
+                    && glyph.unicode.iter().zip(&chars[pos ..]).all(|(&a, &(_, b, _))| a == b)
+            });
+            match found {
+                Some(&gid) => {
+                    gids.push((idx, GlyphId(gid)));
+                    pos += self.glyphs[gid as usize].unicode.len();
+                }
+                None => {
+                    gids.push((idx, GlyphId(0)));
+                    pos += 1;
+                }
+            }
+        }
+        gids
+    }
+}
+
+impl font::Font for SvgFont {
+    fn num_glyphs(&self) -> u32 {
+        self.glyphs.len() as u32
+    }
+    fn font_matrix(&self) -> Transform2F {
+        let s = 1.0 / self.units_per_em;
+        Transform2F::from_scale(vec2f(s, s))
+    }
+    fn glyph(&self, gid: GlyphId) -> Option<Glyph> {
+        let glyph = self.glyphs.get(gid.0 as usize)?;
+        Some(Glyph {
+            metrics: HMetrics { advance: glyph.advance, .. HMetrics::default() },
+            path: glyph.outline.clone(),
+        })
+    }
+    /// a glyph for the character alone, preferring the ones without or with the isolated form
+    fn gid_for_unicode_codepoint(&self, codepoint: u32) -> Option<GlyphId> {
+        let c = std::char::from_u32(codepoint)?;
+        let single: Vec<u32> = self.by_char.get(&c)?.iter().cloned()
+            .filter(|&gid| self.glyphs[gid as usize].unicode.len() == 1)
+            .collect();
+        single.iter().find(|&&gid| match self.glyphs[gid as usize].arabic_form {
+            None | Some(ArabicForm::Isolated) => true,
+            _ => false
+        }).or(single.first()).map(|&gid| GlyphId(gid))
+    }
+    fn gid_for_name(&self, name: &str) -> Option<GlyphId> {
+        self.by_name.get(name).map(|&gid| GlyphId(gid))
+    }
+    fn vmetrics(&self) -> Option<VMetrics> {
+        Some(VMetrics { ascent: self.ascent, descent: self.descent, .. VMetrics::default() })
+    }
+    fn kerning(&self, left: GlyphId, right: GlyphId) -> f32 {
+        self.kerning.get(&(left.0, right.0)).map_or(0.0, |&k| -k)
+    }
+    fn name(&self) -> &Name {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+fn test_glyph(unicode: &str, arabic_form: Option<ArabicForm>) -> SvgGlyphData {
+    SvgGlyphData { unicode: unicode.chars().collect(), names: vec![], outline: Outline::new(), advance: None, arabic_form }
+}
+
+#[cfg(test)]
+fn test_font(glyphs: Vec<SvgGlyphData>, kerning: Vec<SvgKernPair>) -> SvgFont {
+    SvgFont::new(&SvgFontData {
+        family: Some("Test".into()),
+        weight: 400,
+        slant: Slant::Normal,
+        units_per_em: 1000.,
+        ascent: None,
+        descent: None,
+        x_height: None,
+        cap_height: None,
+        underline_position: None,
+        underline_thickness: None,
+        strikeout_position: None,
+        strikeout_thickness: None,
+        advance: 500.,
+        missing_glyph: None,
+        glyphs,
+        kerning,
+    })
+}
+
+#[test]
+fn test_shape() {
+    use font::Font;
+    use ArabicForm::*;
+    let font = test_font(vec![
+        test_glyph("fi", None),
+        test_glyph("f", None),
+        test_glyph("i", None),
+        test_glyph("if", None),
+        test_glyph("\u{628}", Some(Initial)),
+        test_glyph("\u{628}", Some(Isolated)),
+    ], vec![]);
+    let shape = |text: &str, form| -> Vec<(usize, u32)> {
+        let chars: Vec<_> = text.char_indices().map(|(idx, c)| (idx, c, form)).collect();
+        font.shape(&chars).into_iter().map(|(idx, gid)| (idx, gid.0)).collect()
+    };
+    // the ligature comes before its parts, the parts come before "if"
+    assert_eq!(shape("fif", Isolated), [(0, 1), (2, 2)]);
+    assert_eq!(shape("iff", Isolated), [(0, 3), (1, 2), (2, 2)]);
+    // the missing glyph for characters without a glyph
+    assert_eq!(shape("fxi", Isolated), [(0, 2), (1, 0), (2, 3)]);
+
+    // glyphs with an arabic form only match that form
+    assert_eq!(shape("\u{628}", Initial), [(0, 5)]);
+    assert_eq!(shape("\u{628}", Isolated), [(0, 6)]);
+    assert_eq!(shape("\u{628}", Terminal), [(0, 0)]);
+    // without a form the isolated one is preferred
+    assert_eq!(font.gid_for_unicode_codepoint(0x628).map(|gid| gid.0), Some(6));
+}
+
+#[test]
+fn test_kerning() {
+    use font::Font;
+    let named = |unicode, name: &str| SvgGlyphData { names: vec![name.into()], .. test_glyph(unicode, None) };
+    let font = test_font(vec![named("f", "eff"), test_glyph("i", None), test_glyph("fi", None)], vec![
+        SvgKernPair { u1: vec![], g1: vec!["eff".into()], u2: vec![('a', 'j')], g2: vec![], k: 50. },
+        SvgKernPair { u1: vec![('f', 'f')], g1: vec![], u2: vec![('i', 'i')], g2: vec![], k: 20. },
+    ]);
+    // the first matching pair wins and moves the glyphs closer
+    assert_eq!(font.kerning(GlyphId(1), GlyphId(2)), -50.);
+    assert_eq!(font.kerning(GlyphId(2), GlyphId(1)), 0.);
+    // ligatures are not matched by their characters
+    assert_eq!(font.kerning(GlyphId(1), GlyphId(3)), 0.);
+}
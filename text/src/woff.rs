@@ -0,0 +1,154 @@
+//! unpacking of WOFF 1.0 files into plain sfnt data
+
+use crate::metrics::{u16_at, u32_at, tag_at};
+use std::convert::TryFrom;
+use std::io::Read;
+
+/// the most a deflate stream can expand its input
+const MAX_DEFLATE_RATIO: usize = 1032;
+
+/// the sfnt data of a WOFF file, `None` if `data` is not a valid WOFF file
+pub fn unpack(data: &[u8]) -> Option<Vec<u8>> {
+    if data.get(.. 4)? != b"wOFF" {
+        return None;
+    }
+    let flavor = u32_at(data, 4)?;
+    let num_tables = u16_at(data, 12)? as usize;
+
+    let mut tables = Vec::with_capacity(num_tables);
+    for entry in (0 .. num_tables).map(|i| 44 + 20 * i) {
+        let tag = tag_at(data, entry)?;
+        let offset = u32_at(data, entry + 4)? as usize;
+        let comp_length = u32_at(data, entry + 8)? as usize;
+        let orig_length = u32_at(data, entry + 12)? as usize;
+        let checksum = u32_at(data, entry + 16)?;
+        let stored = data.get(offset .. offset.checked_add(comp_length)?)?;
+        // a length the stored data can't inflate to is a lie, don't trust it for allocations
+        if orig_length > comp_length.saturating_mul(MAX_DEFLATE_RATIO) {
+            return None;
+        }
+        let table = match comp_length < orig_length {
+            true => {
+                // one byte more than expected is enough to tell that the table is too long
+                let mut table = Vec::new();
+                libflate::zlib::Decoder::new(stored).ok()?
+                    .take(orig_length as u64 + 1)
+                    .read_to_end(&mut table).ok()?;
+                table
+            }
+            false => stored.to_vec(),
+        };
+        if table.len() != orig_length {
+            return None;
+        }
+        tables.push((tag, checksum, table));
+    }
+
+    // the search fields of the directory are 16 bit, which limits it to 4095 tables
+    let entry_selector = 31 - (num_tables.max(1) as u32).leading_zeros();
+    let search_range = 16u32 << entry_selector;
+    let range_shift = (16 * num_tables as u32).saturating_sub(search_range);
+    let search_range = u16::try_from(search_range).ok()?;
+    let range_shift = u16::try_from(range_shift).ok()?;
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&flavor.to_be_bytes());
+    out.extend_from_slice(&(num_tables as u16).to_be_bytes());
+    out.extend_from_slice(&search_range.to_be_bytes());
+    out.extend_from_slice(&(entry_selector as u16).to_be_bytes());
+    out.extend_from_slice(&range_shift.to_be_bytes());
+
+    // tables start after the directory and are 4-byte aligned
+    let mut offset = 12 + 16 * num_tables;
+    for &(tag, checksum, ref table) in &tables {
+        out.extend_from_slice(tag);
+        out.extend_from_slice(&checksum.to_be_bytes());
+        out.extend_from_slice(&u32::try_from(offset).ok()?.to_be_bytes());
+        out.extend_from_slice(&(table.len() as u32).to_be_bytes());
+        offset += (table.len() + 3) & !3;
+    }
+    for (_, _, table) in tables {
+        out.extend_from_slice(&table);
+        out.resize((out.len() + 3) & !3, 0);
+    }
+    Some(out)
+}
+
+/// a WOFF file with the given tables, which are compressed if that makes them smaller
+#[cfg(test)]
+fn test_woff(tables: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut data = b"wOFF".to_vec();
+    data.extend_from_slice(&0x00010000u32.to_be_bytes());
+    data.extend_from_slice(&[0; 4]);
+    data.extend_from_slice(&(tables.len() as u16).to_be_bytes());
+    data.resize(44 + 20 * tables.len(), 0);
+    for (i, &(tag, table)) in tables.iter().enumerate() {
+        let mut encoder = libflate::zlib::Encoder::new(Vec::new()).unwrap();
+        encoder.write_all(table).unwrap();
+        let compressed = encoder.finish().into_result().unwrap();
+        let stored = match compressed.len() < table.len() {
+            true => compressed,
+            false => table.to_vec(),
+        };
+        let (entry, offset) = (44 + 20 * i, data.len() as u32);
+        data[entry .. entry + 4].copy_from_slice(tag);
+        data[entry + 4 .. entry + 8].copy_from_slice(&offset.to_be_bytes());
+        data[entry + 8 .. entry + 12].copy_from_slice(&(stored.len() as u32).to_be_bytes());
+        data[entry + 12 .. entry + 16].copy_from_slice(&(table.len() as u32).to_be_bytes());
+        data.extend_from_slice(&stored);
+    }
+    data
+}
+
+#[test]
+fn test_unpack() {
+    use crate::metrics::table;
+
+    let head = [1u8; 54];
+    let name = b"abc";
+    let sfnt = unpack(&test_woff(&[(b"head", &head), (b"name", name)])).unwrap();
+    assert_eq!(u32_at(&sfnt, 0), Some(0x00010000));
+    // numTables, searchRange, entrySelector, rangeShift
+    let directory: Vec<_> = (4 .. 12).step_by(2).map(|i| u16_at(&sfnt, i).unwrap()).collect();
+    assert_eq!(directory, [2, 32, 1, 0]);
+    assert_eq!(table(&sfnt, b"head"), Some(&head[..]));
+    assert_eq!(table(&sfnt, b"name"), Some(&name[..]));
+    // tables are 4-byte aligned
+    assert_eq!(u32_at(&sfnt, 12 + 16 + 8), Some(12 + 32 + 56));
+
+    assert_eq!(unpack(b"OTTO"), None);
+    let mut truncated = test_woff(&[(b"head", &head)]);
+    truncated.pop();
+    assert_eq!(unpack(&truncated), None);
+}
+
+#[test]
+fn test_unpack_table_count() {
+    let empty = vec![(b"abcd", &[][..]); 4095];
+    let sfnt = unpack(&test_woff(&empty)).unwrap();
+    let directory: Vec<_> = (4 .. 12).step_by(2).map(|i| u16_at(&sfnt, i).unwrap()).collect();
+    assert_eq!(directory, [4095, 32768, 11, 65520 - 32768]);
+
+    // the search range of 4096 tables doesn't fit in 16 bits
+    let empty = vec![(b"abcd", &[][..]); 4096];
+    assert_eq!(unpack(&test_woff(&empty)), None);
+}
+
+#[test]
+fn test_unpack_lengths() {
+    let table = [0u8; 4096];
+    let woff = test_woff(&[(b"head", &table)]);
+    let with_length = |length: u32| {
+        let mut woff = woff.clone();
+        woff[44 + 12 .. 44 + 16].copy_from_slice(&length.to_be_bytes());
+        unpack(&woff)
+    };
+    assert!(with_length(4096).is_some());
+    // the table inflates to more or less than it claims
+    assert_eq!(with_length(4095), None);
+    assert_eq!(with_length(4097), None);
+    // more than the stored data can inflate to
+    assert_eq!(with_length(u32::MAX), None);
+}
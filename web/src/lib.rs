@@ -23,7 +23,7 @@ impl Interactive for SvgView {
         "SVG".into()
     }
     fn scene(&mut self, ctx: &mut Context) -> Scene {
-        DrawContext::new(&self.svg, &self.fonts)
+        DrawContext::new(&self.svg, self.fonts.clone())
         .compose_with_transform(
             Transform2F::from_scale(25.4 / 75.)
         )